@@ -1,82 +1,1387 @@
 //! `Radio` trait implementation for the `esp-hal` ESP IEEE 802.15.4 radio.
 
+use core::cell::Cell;
+use core::pin::pin;
+
+use embassy_futures::select::{select, Either};
+
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
 use embassy_sync::signal::Signal;
 
-use esp_radio::ieee802154::Config as EspConfig;
+use embassy_time::{with_deadline, Duration, Instant, Timer};
+
+use esp_radio::ieee802154::{Config as EspConfig, RawReceived};
+
+use portable_atomic::{AtomicBool, Ordering};
+
+use crate::fmt::{bitflags, Bytes};
+use crate::radio::mac::MacHeader;
+use crate::radio::phy;
+use crate::sys::OT_RADIO_FRAME_MAX_SIZE;
+use crate::{
+    Capabilities, Cca, Config, EnergyScanResult, MacCapabilities, PsduMeta, Radio, RadioErrorKind,
+    SecurityLevels, TxOutcome,
+};
+
+pub use esp_radio::ieee802154::Ieee802154;
+
+/// The `esp-hal` ESP IEEE 802.15.4 radio.
+pub struct EspRadio<'a> {
+    driver: Ieee802154<'a>,
+    config: Config,
+    rx_queue_size: usize,
+    last_raw: Option<RawReceived>,
+    lenient_ack: bool,
+    enhanced_ack: bool,
+    silent: bool,
+    tx_glitch_retries: u8,
+    idle_after_tx: bool,
+    last_tx_outcome: Option<TxOutcome>,
+    promiscuous_rate_limit: Option<RateLimit>,
+    strict_rx_length: bool,
+    last_ack_seq: Option<u8>,
+    channel_settled_at: Option<Instant>,
+    tx_tuned: bool,
+    last_activity: Instant,
+    receiving: bool,
+    liveness_timeout: Option<Duration>,
+    stats: Stats,
+    debug_gpio: Option<DebugGpio<'a>>,
+}
+
+bitflags! {
+    /// Radio events on which a debug GPIO pulse can be emitted.
+    /// See [`EspRadio::set_debug_gpio`].
+    #[repr(transparent)]
+    #[derive(Default)]
+    #[cfg_attr(not(feature = "defmt"), derive(Debug, Copy, Clone, Eq, PartialEq, Hash))]
+    pub struct DebugEvents: u8 {
+        /// A frame is about to be handed over to the radio for transmission.
+        const TX_START = 0x01;
+        /// The radio reported a transmission as successfully completed.
+        const TX_DONE = 0x02;
+        /// An ACK for the transmitted frame was received.
+        const ACK_RECEIVED = 0x04;
+        /// A received frame was taken from the radio receive queue.
+        const RX_FRAME = 0x08;
+    }
+}
+
+impl<'a> EspRadio<'a> {
+    const DEFAULT_CONFIG: Config = Config::new();
+
+    /// Default esp-radio receive-queue depth (frames buffered before drops).
+    ///
+    /// esp-radio's own default of 10 is too small for OpenThread's RX bursts; 50
+    /// is a safer baseline. Bursty Matter commissioning/SRP may need more — see
+    /// [`EspRadio::with_rx_queue_size`].
+    pub const DEFAULT_RX_QUEUE_SIZE: usize = 50;
+
+    /// The lowest channel the ESP 802.15.4 radio supports (2.4 GHz O-QPSK PHY, channel page 0).
+    pub const CHANNEL_MIN: u8 = 11;
+    /// The highest channel the ESP 802.15.4 radio supports.
+    pub const CHANNEL_MAX: u8 = 26;
+
+    /// The lowest CCA ED threshold (in dBm) the ESP 802.15.4 radio accepts.
+    ///
+    /// The ESP radio takes the ED threshold as a signed dBm value; ESP-IDF documents the
+    /// valid range of its `IEEE802154_CCA_THRESHOLD` setting as -120..=0 dBm.
+    pub const CCA_THRESHOLD_MIN: i8 = -120;
+    /// The highest CCA ED threshold (in dBm) the ESP 802.15.4 radio accepts.
+    pub const CCA_THRESHOLD_MAX: i8 = 0;
+
+    /// For how long esp-radio waits for the ACK of a transmitted frame before reporting the
+    /// transmission as failed.
+    ///
+    /// This is the ACK timeout of esp-radio (as of 0.18, matching the one of the ESP-IDF driver),
+    /// much longer than the IEEE 802.15.4 `macAckWaitDuration` (see [`Self::expected_ack_wait`]).
+    pub const DRIVER_ACK_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// The error kinds the transmit methods of `EspRadio` can fail with,
+    /// in the order they are reported by [`EspRadio::tx_failure_breakdown`].
+    pub const TX_FAILURE_KINDS: [RadioErrorKind; 6] = [
+        RadioErrorKind::InvalidFrame,
+        RadioErrorKind::TxFailed,
+        RadioErrorKind::RxAckInvalid,
+        RadioErrorKind::Busy,
+        RadioErrorKind::Silenced,
+        RadioErrorKind::DeadlineMissed,
+    ];
+
+    /// The default time (in microseconds) to wait after a channel change before the next
+    /// transmission or reception, used unless [`Config::channel_settle_us`] is set.
+    ///
+    /// IEEE 802.15.4 bounds the time a radio needs to switch channels by `aTurnaroundTime`
+    /// (12 symbols, i.e. 192us), so wait for that long for the PLL to lock on the new frequency.
+    pub const DEFAULT_CHANNEL_SETTLE_US: u32 = phy::TURNAROUND_SYMBOLS * phy::SYMBOL_US;
+
+    /// Create a new `EspRadio` instance.
+    pub fn new(ieee802154: Ieee802154<'a>) -> Self {
+        let mut this = Self {
+            driver: ieee802154,
+            config: Self::DEFAULT_CONFIG,
+            rx_queue_size: Self::DEFAULT_RX_QUEUE_SIZE,
+            last_raw: None,
+            lenient_ack: true,
+            enhanced_ack: true,
+            silent: false,
+            tx_glitch_retries: 0,
+            idle_after_tx: false,
+            last_tx_outcome: None,
+            promiscuous_rate_limit: None,
+            strict_rx_length: false,
+            last_ack_seq: None,
+            channel_settled_at: None,
+            tx_tuned: false,
+            last_activity: Instant::now(),
+            receiving: false,
+            liveness_timeout: None,
+            stats: Stats::new(),
+            debug_gpio: None,
+        };
+
+        this.driver.set_rx_available_callback_fn(Self::rx_callback);
+        this.driver.set_tx_done_callback_fn(Self::tx_done_callback);
+        this.driver
+            .set_tx_failed_callback_fn(Self::tx_failed_callback);
+
+        TX_COMPLETE_CALLBACK.lock(|callback| callback.set(None));
+
+        this.update_driver_config();
+
+        this
+    }
+
+    /// Set the esp-radio receive-queue depth: frames buffered before further
+    /// incoming frames are dropped (logged as `Receive queue full`).
+    ///
+    /// Raising the default ([`Self::DEFAULT_RX_QUEUE_SIZE`], e.g. to 200) gives
+    /// headroom when the OpenThread consumer stalls under bursty load (e.g.
+    /// crypto during commissioning). Each queued frame is a heap buffer of
+    /// ~130 bytes, allocated on demand and not freed until reset, so the depth
+    /// caps the worst-case RX heap (e.g. 200 ≈ 26 KB).
+    #[must_use]
+    pub fn with_rx_queue_size(mut self, rx_queue_size: usize) -> Self {
+        self.rx_queue_size = rx_queue_size;
+        self.update_driver_config();
+        self
+    }
+
+    /// Set how ACK frames too short to be a valid Imm-ACK (FCF + sequence number + FCS,
+    /// i.e. 5 bytes) are handled by [`Radio::transmit`].
+    ///
+    /// By default (`lenient = true`) such a malformed ACK is passed on as received, as any
+    /// other ACK; it can be told apart from a missing ACK by its length, if an `ack_psdu_buf`
+    /// is supplied. With `lenient = false`, it is reported as [`RadioErrorKind::RxAckInvalid`]
+    /// instead, whether or not an `ack_psdu_buf` is supplied.
+    ///
+    /// Independently of this setting, an ACK which does not fit in the supplied `ack_psdu_buf`
+    /// is reported as [`RadioErrorKind::BufferTooSmall`] rather than as a missing ACK.
+    #[must_use]
+    pub fn with_lenient_ack(mut self, lenient: bool) -> Self {
+        self.lenient_ack = lenient;
+        self
+    }
+
+    /// Set whether the radio auto-ACKs received ACK-requesting frame-version-2 (IEEE 802.15.4-2015)
+    /// frames with an Enh-ACK.
+    ///
+    /// Enabled by default. Frame-version-0 and -1 frames are always auto-ACKed with an Imm-ACK.
+    ///
+    /// NOTE: esp-radio (as of 0.18) only turns on the hardware Enh-ACK generation; unlike ESP-IDF,
+    /// it does not call into OpenThread to build the Enh-ACK frame. The generated Enh-ACKs
+    /// therefore carry no IEs (i.e. no CSL or Link Metrics IEs) and are not secured, even if the
+    /// acknowledged frame is, which is not sufficient for full Thread 1.2 interoperability
+    /// (CSL receivers, Enh-ACK based probing). Disabling Enh-ACKs leaves frame-version-2 frames
+    /// un-ACKed by the radio.
+    #[must_use]
+    pub fn with_enhanced_ack(mut self, enable: bool) -> Self {
+        self.enhanced_ack = enable;
+        self.update_driver_config();
+        self
+    }
+
+    /// Set whether [`Radio::receive`] verifies the PHY length byte of received frames.
+    ///
+    /// By default (`strict = false`) the PSDU length is taken from the lower 7 bits of
+    /// the length byte, and silently truncated to the data esp-radio copied to its queue slot.
+    /// With `strict = true`, a frame whose length byte declares more data than available (i.e.
+    /// more than the esp-radio queue slot holds, or more than the 127 bytes of the largest
+    /// IEEE 802.15.4 frame) is rejected with [`RadioErrorKind::InvalidFrame`] (and counted as
+    /// dropped) instead, which surfaces framing bugs at the driver level.
+    ///
+    /// See also [`EspRadio::rx_hardware_overflows`].
+    #[must_use]
+    pub fn with_strict_rx_length(mut self, strict: bool) -> Self {
+        self.strict_rx_length = strict;
+        self
+    }
+
+    /// Set the number of immediate retries of a transmission the radio reported as failed,
+    /// before [`Radio::transmit`] reports [`RadioErrorKind::TxFailed`].
+    ///
+    /// This absorbs transient TX failures on marginal hardware which a single immediate retry
+    /// would fix, without bubbling them up to the OpenThread SubMac (and its own, slower,
+    /// retry and backoff logic). These retries are distinct from the CSMA backoffs done by
+    /// the radio for each attempt.
+    ///
+    /// Each retry is a complete new TX attempt (CCA, if requested, plus the frame airtime and -
+    /// if an ACK is requested - the ACK wait), so a failing `transmit` takes up to
+    /// `retries + 1` times longer to report the failure. The callback set with
+    /// [`Self::set_tx_complete_callback`] is still invoked on every failed attempt.
+    ///
+    /// Default is 0, i.e. the failure is reported right away.
+    #[must_use]
+    pub fn with_tx_glitch_retries(mut self, retries: u8) -> Self {
+        self.tx_glitch_retries = retries;
+        self
+    }
+
+    /// Limit the number of frames per second delivered by [`Radio::receive`] while the radio
+    /// is in promiscuous (sniffer) mode; `None` (the default) means unlimited.
+    ///
+    /// On a busy channel, a sniffer might otherwise be overwhelmed by the frame rate and fall
+    /// further and further behind (e.g. when streaming the frames over RTT). Frames above the
+    /// limit are dropped right after being taken from the esp-radio queue, and are counted in
+    /// [`Self::rx_rate_limited`]. The limit does not apply outside of promiscuous mode.
+    #[must_use]
+    pub fn with_promiscuous_rate_limit(mut self, frames_per_sec: Option<u32>) -> Self {
+        self.promiscuous_rate_limit = frames_per_sec.map(RateLimit::new);
+        self
+    }
+
+    /// Reset the radio (see [`Self::reset`]) when an RX operation observes no radio activity
+    /// (no frame received and no transmission completed) for longer than `timeout`;
+    /// `None` (the default) disables the liveness check.
+    ///
+    /// This catches the case of a wedged driver which silently stops receiving, which would
+    /// otherwise go unnoticed until a reboot. The resets done are counted in
+    /// [`Self::liveness_resets`].
+    ///
+    /// NOTE: Only use this on channels known to carry regular traffic (e.g. a Thread network
+    /// with MLE advertisements every few tens of seconds), and with a timeout comfortably larger
+    /// than the longest expected silence: on a quiet channel, a timeout which is too short results
+    /// in spurious resets, each of which aborts whatever the radio is doing.
+    #[must_use]
+    pub fn with_liveness_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.liveness_timeout = timeout;
+        self
+    }
+
+    /// Set a callback to be invoked whenever a transmission completes, complementing
+    /// the async [`Radio::transmit`].
+    ///
+    /// Useful for event-driven / message-passing designs which would like to react
+    /// to TX completions without an awaiting task.
+    ///
+    /// The callback is invoked from the esp-radio TX-done / TX-failed interrupt
+    /// handlers, so it must be short and non-blocking, and it must not call back into
+    /// `EspRadio` (or anything else which might be holding the radio). Posting to a
+    /// channel or a signal is the intended use.
+    ///
+    /// Since the frame is not available in the interrupt handlers, the callback only reports
+    /// [`TxOutcome::Sent`] or [`TxOutcome::Failed`]; see [`Self::last_tx_outcome`] for
+    /// the delivery confirmation.
+    pub fn set_tx_complete_callback(&mut self, callback: fn(TxOutcome)) {
+        TX_COMPLETE_CALLBACK.lock(|cb| cb.set(Some(callback)));
+    }
+
+    /// Clear the callback set with [`Self::set_tx_complete_callback`].
+    pub fn clear_tx_complete_callback(&mut self) {
+        TX_COMPLETE_CALLBACK.lock(|cb| cb.set(None));
+    }
+
+    /// Emit a short pulse (high, then low) via `pin` whenever one of the selected `events` occurs,
+    /// so that the radio activity can be correlated with the actual RF timing on an oscilloscope.
+    ///
+    /// `pin` is expected to drive a GPIO to the requested level (`true` = high), e.g.
+    /// `|high| output.set_level(high.into())`.
+    ///
+    /// NOTE: esp-radio does not give access to the radio hardware events (SFD detection, TX start
+    /// on-air etc.), so the pulses are emitted by `EspRadio` itself, when it observes the event:
+    /// - [`DebugEvents::TX_START`]: right before the frame is handed over to the radio; the
+    ///   frame goes on-air after the CCA (if requested) and the RX-to-TX turnaround, i.e.
+    ///   typically 200-400us later;
+    /// - [`DebugEvents::TX_DONE`] and [`DebugEvents::ACK_RECEIVED`]: when the awaiting task is
+    ///   woken up by the TX-done interrupt, i.e. tens of microseconds after the end of the
+    ///   transmitted frame (or the ACK), depending on the executor load;
+    /// - [`DebugEvents::RX_FRAME`]: when the frame is taken from the receive queue by
+    ///   [`Radio::receive`], which can be arbitrarily late after the frame was actually received,
+    ///   if the frame had to be queued.
+    pub fn set_debug_gpio(&mut self, pin: &'a mut (dyn FnMut(bool) + Send), events: DebugEvents) {
+        self.debug_gpio = Some(DebugGpio { pin, events });
+    }
+
+    /// Stop emitting the debug GPIO pulses set with [`Self::set_debug_gpio`].
+    pub fn clear_debug_gpio(&mut self) {
+        self.debug_gpio = None;
+    }
+
+    /// Return the raw bytes of the most recently received frame, exactly as
+    /// delivered by esp-radio: the leading PHY length byte, the PSDU, and any
+    /// trailing metadata (e.g. RSSI) and buffer slack.
+    ///
+    /// Useful for debugging framing issues (e.g. the `& 0x7f` length masking
+    /// done in [`Radio::receive`]) without a sniffer. The frame is captured
+    /// even if `receive` subsequently rejects it.
+    ///
+    /// Returns `None` if no frame has been received yet. The returned data is
+    /// overwritten by the next `receive` call.
+    pub fn last_raw_frame(&self) -> Option<&[u8]> {
+        self.last_raw.as_ref().map(|raw| raw.data.as_slice())
+    }
+
+    /// Return the sequence number of the last ACK auto-generated by the radio, if any.
+    ///
+    /// Useful for diagnosing ACK mismatches, where the peer rejects the ACKs sent by the radio.
+    ///
+    /// esp-radio does not report the ACKs it sends, so this is the sequence number of the last
+    /// frame delivered by [`Radio::receive`] which requested an ACK (the radio echoes the
+    /// sequence number of the acknowledged frame in the ACK). Frames received in promiscuous
+    /// mode are not auto-acknowledged and are therefore not taken into account.
+    pub fn last_ack_seq(&self) -> Option<u8> {
+        self.last_ack_seq
+    }
+
+    /// Receive all frames currently queued by the driver in one go, invoking `f` with the PSDU
+    /// and the meta-data of each frame, and return the number of frames delivered to `f`.
+    ///
+    /// If no frame is queued yet, waits for at least one to arrive first. The frames are delivered
+    /// in the order in which they were received, and the queue is drained; frames which fail
+    /// the checks of [`Radio::receive`] are dropped (and counted as such in [`EspRadio::rx_stats`]).
+    ///
+    /// Compared to calling [`Radio::receive`] in a loop, this amortizes the awaiting over all
+    /// frames queued during bursts. The PSDU slice passed to `f` is only valid for the duration of
+    /// the call - copy it if it needs to outlive the closure.
+    pub async fn receive_batch<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&[u8], &PsduMeta),
+    {
+        let mut psdu_buf = [0_u8; OT_RADIO_FRAME_MAX_SIZE as _];
+
+        RX_SIGNAL.reset();
+
+        // In case a transmission future was dropped while tuned to the TX channel
+        self.tune(false);
+        self.wait_channel_settled().await;
+
+        trace!(
+            "802.15.4: About to RX a batch on ch{}",
+            self.config.effective_rx_channel()
+        );
+
+        self.start_receive();
+        self.receiving = true;
+
+        let mut raw = loop {
+            if let Some(frame) = self.pop_received() {
+                break frame;
+            }
+
+            self.wait_rx_signal().await;
+        };
+
+        self.receiving = false;
+
+        let mut delivered = 0;
+
+        loop {
+            if let Ok(psdu_meta) = self.process_received(raw, &mut psdu_buf) {
+                f(&psdu_buf[..psdu_meta.len], &psdu_meta);
+                delivered += 1;
+            }
+
+            let Some(next) = self.pop_received() else {
+                break;
+            };
+
+            raw = next;
+        }
+
+        trace!("802.15.4: Received a batch of {} frames", delivered);
+
+        delivered
+    }
+
+    /// Return the outcome of the last transmission, or `None` if nothing was transmitted yet.
+    ///
+    /// Useful to get a delivery confirmation ([`TxOutcome::Acked`]) for a frame which requested
+    /// an ACK, when [`Radio::transmit`] was called without an `ack_psdu_buf` (and therefore
+    /// returned `Ok(None)` regardless of whether an ACK was received). The outcome also carries
+    /// the frame-pending bit of the ACK, e.g. for a sleepy end device which sent a data request.
+    pub fn last_tx_outcome(&self) -> Option<TxOutcome> {
+        self.last_tx_outcome
+    }
+
+    /// Abort the in-flight transmission (if any) by switching the radio back to receive.
+    ///
+    /// A pending outcome of the aborted transmission is discarded.
+    ///
+    /// Note that dropping the future of a transmission does not abort it: until the radio
+    /// reports the completion of the dropped transmission (which takes up to the airtime of the
+    /// frame plus the ACK timeout of the driver, see [`Self::DRIVER_ACK_TIMEOUT`]), further
+    /// transmissions immediately fail with [`RadioErrorKind::Busy`] rather than waiting for it.
+    /// Call this method after dropping a transmission to be able to transmit again right away.
+    pub fn abort_transmit(&mut self) {
+        trace!("802.15.4: Aborting TX");
+
+        self.start_receive();
+        TX_SIGNAL.reset();
+    }
+
+    /// Enable or disable the radio silence mode.
+    ///
+    /// While silent, the radio does not transmit anything: [`Radio::transmit`] (and the other
+    /// transmit methods) fail with [`RadioErrorKind::Silenced`], and received frames are not
+    /// auto-ACKed, so that the node is a pure listener (e.g. for spectrum measurements and
+    /// regulatory listening tests). Receiving and energy scans are not affected.
+    ///
+    /// Note that while silent, the node is effectively unreachable: its neighbors see all of
+    /// their unicast transmissions to it fail, and might eventually drop it from the network.
+    /// A transmission already in flight when the silence is enabled is aborted.
+    pub fn set_radio_silence(&mut self, silent: bool) {
+        if self.silent != silent {
+            debug!("802.15.4: Radio silence: {}", silent);
+
+            self.silent = silent;
+
+            if silent {
+                self.abort_transmit();
+            }
+
+            self.update_driver_config();
+        }
+    }
+
+    /// Return `true` if the radio is in radio silence mode.
+    /// See [`EspRadio::set_radio_silence`].
+    pub fn is_radio_silenced(&self) -> bool {
+        self.silent
+    }
+
+    /// Reset the radio to a known state, e.g. to recover from an error.
+    ///
+    /// A transmission still in flight (i.e. of a [`Radio::transmit`] future which was dropped)
+    /// is aborted, and any pending TX / RX outcome is discarded, so that it cannot be mistaken
+    /// for the outcome of the next operation. The configuration is then re-applied to the driver.
+    ///
+    /// The radio is left receiving.
+    pub fn reset(&mut self) {
+        debug!("802.15.4: Reset");
+
+        self.start_receive();
+
+        TX_SIGNAL.reset();
+        RX_SIGNAL.reset();
+
+        self.idle_after_tx = false;
+        self.update_driver_config();
+    }
+
+    /// Transmit a radio frame, giving up if the transmission cannot be completed by `deadline`.
+    ///
+    /// Useful for latency-bounded traffic, where a frame which cannot be sent in time (e.g.
+    /// due to CSMA backoffs on a busy channel) is worthless and should rather be dropped
+    /// than delivered late.
+    ///
+    /// Since esp-radio does not report when the frame actually goes on-air, the deadline
+    /// applies to the completion of the transmission (including the reception of the ACK,
+    /// if one is requested). If the deadline passes first, the transmission is aborted
+    /// with [`Self::abort_transmit`] and [`RadioErrorKind::DeadlineMissed`] is returned.
+    /// A frame which already started going on-air when it is aborted might still be
+    /// (partially) transmitted.
+    ///
+    /// Arguments and return value are as with [`Radio::transmit`], plus:
+    /// - `deadline`: The instant by which the transmission should be complete.
+    pub async fn transmit_by(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        deadline: Instant,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, RadioErrorKind> {
+        if Instant::now() >= deadline {
+            trace!("802.15.4: TX deadline already passed");
+            self.count_tx_failure(RadioErrorKind::DeadlineMissed);
+            return Err(RadioErrorKind::DeadlineMissed);
+        }
+
+        let result = {
+            let mut tx = pin!(self.transmit(psdu, cca, ack_psdu_buf));
+            let mut timeout = pin!(Timer::at(deadline));
+
+            select(&mut tx, &mut timeout).await
+        };
+
+        match result {
+            Either::First(result) => result,
+            Either::Second(_) => {
+                trace!("802.15.4: TX deadline missed");
+
+                self.abort_transmit();
+                self.count_tx_failure(RadioErrorKind::DeadlineMissed);
+
+                Err(RadioErrorKind::DeadlineMissed)
+            }
+        }
+    }
+
+    /// Perform an energy scan on `channel` for `duration`.
+    ///
+    /// NOTE: esp-radio does not expose the energy detection (ED) of the radio, so this is not
+    /// an ED measurement: [`EnergyScanResult::peak_rssi`] is the peak RSSI of the 802.15.4 frames
+    /// the radio decoded during the scan, and is `None` if no frame was decoded - even if the
+    /// channel is occupied by a non-802.15.4 interferer. [`EnergyScanResult::frames_seen`] is the
+    /// number of these frames, so that a channel busy with 802.15.4 traffic can be told apart from
+    /// a quiet one.
+    ///
+    /// The frames received during the scan are not delivered to [`Radio::receive`], and are not
+    /// accounted for in the RX statistics. The frames received on the configured channel but not
+    /// yet returned by [`Radio::receive`] when the scan starts are discarded (and counted as
+    /// dropped in [`Self::rx_stats`]), so that they are not mistaken for frames of the scanned
+    /// channel.
+    ///
+    /// The radio returns to the configured channel(s) once the scan is over, or if the future
+    /// is dropped mid-scan.
+    pub async fn energy_scan(
+        &mut self,
+        channel: u8,
+        duration: Duration,
+    ) -> Result<EnergyScanResult, RadioErrorKind> {
+        trace!(
+            "802.15.4: Energy scan on ch{} for {}ms",
+            channel,
+            duration.as_millis()
+        );
+
+        let config = self.energy_scan_begin(channel);
+
+        let mut this = scopeguard::guard(self, move |this| this.energy_scan_end(config));
+
+        this.wait_channel_settled().await;
+        this.energy_scan_listen();
+
+        let mut result = EnergyScanResult::default();
+
+        let deadline = Instant::now() + duration;
+
+        loop {
+            this.energy_scan_poll(&mut result);
+
+            if with_deadline(deadline, RX_SIGNAL.wait()).await.is_err() {
+                break;
+            }
+        }
+
+        trace!("802.15.4: Energy scan on ch{} done: {:?}", channel, result);
+
+        Ok(result)
+    }
+
+    /// Perform an energy scan on `channel` for `duration_us` microseconds, without an
+    /// async executor, by busy-polling the radio.
+    ///
+    /// Useful in bootloader, diagnostics and factory test contexts, where the radio needs to
+    /// be used before (or without) an executor. The measurement is the same as with
+    /// [`Self::energy_scan`] (including its limitations), except that only the peak RSSI is
+    /// returned: `None` if no frame was decoded during the scan, which - as this is not an ED
+    /// measurement - does not necessarily mean that the channel is quiet.
+    ///
+    /// NOTE: This method blocks the CPU for the whole `duration_us` (plus the channel settle time).
+    pub fn energy_scan_blocking(&mut self, channel: u8, duration_us: u32) -> Option<i8> {
+        trace!(
+            "802.15.4: Blocking energy scan on ch{} for {}us",
+            channel,
+            duration_us
+        );
+
+        let config = self.energy_scan_begin(channel);
+
+        let mut this = scopeguard::guard(self, move |this| this.energy_scan_end(config));
+
+        if let Some(settled_at) = this.channel_settled_at.take() {
+            while Instant::now() < settled_at {}
+        }
+
+        this.energy_scan_listen();
+
+        let mut result = EnergyScanResult::default();
+
+        let deadline = Instant::now() + Duration::from_micros(duration_us as _);
+
+        while Instant::now() < deadline {
+            this.energy_scan_poll(&mut result);
+        }
+
+        trace!(
+            "802.15.4: Blocking energy scan on ch{} done: {:?}",
+            channel,
+            result
+        );
+
+        result.peak_rssi
+    }
+
+    /// Transmit a radio frame, leaving the radio idle (i.e. not receiving) afterwards,
+    /// regardless of [`Config::rx_when_idle`].
+    ///
+    /// Useful for transmit-heavy operations (e.g. flooding beacons in a test), where returning
+    /// to receive after each frame only wastes power and results in processing irrelevant frames.
+    ///
+    /// Normally, the ESP radio returns to receive after a transmission if `rx_when_idle` is set.
+    /// With this method, the radio stays idle after the transmission (and after the reception
+    /// of its ACK, if one is requested) until the next [`Radio::receive`] call explicitly
+    /// starts receiving, or the next [`Radio::transmit`] call re-applies `rx_when_idle`.
+    ///
+    /// Arguments and return value are as with [`Radio::transmit`].
+    pub async fn transmit_no_rx(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, RadioErrorKind> {
+        self.transmit_with(psdu, cca, ack_psdu_buf, true).await
+    }
+
+    /// Transmit a burst of radio frames back-to-back, reporting the outcome of each frame
+    /// in the corresponding slot of `outcomes`.
+    ///
+    /// Useful for flushing several indirect frames to a sleepy child which is only
+    /// briefly awake.
+    ///
+    /// Every received ACK is matched against the frame it is supposed to acknowledge by
+    /// sequence number. Since the ESP radio reports a TX as completed only once the ACK
+    /// for it had been received (or had timed out), an ACK can never be associated with the
+    /// next frame in the burst; yet, the radio reports the last ACK it received, so an ACK
+    /// whose sequence number does not match the frame (i.e. a stale ACK for an earlier frame)
+    /// is disregarded, and the frame is reported as not acknowledged.
+    ///
+    /// The burst is not interrupted by the failure of an individual frame.
+    ///
+    /// Arguments:
+    /// - `psdus`: The PSDUs of the frames to transmit, in order.
+    /// - `cca`: Whether to perform CCA before transmitting each frame.
+    /// - `outcomes`: Receives the outcome of each frame, with the same meaning as the return
+    ///   value of [`Radio::transmit`].
+    ///
+    /// Returns the number of frames transmitted (and of outcomes reported), i.e. the shorter of
+    /// the lengths of `psdus` and `outcomes`: the frames without a slot in `outcomes` are not
+    /// transmitted.
+    pub async fn transmit_burst(
+        &mut self,
+        psdus: &[&[u8]],
+        cca: bool,
+        outcomes: &mut [Result<Option<PsduMeta>, RadioErrorKind>],
+    ) -> usize {
+        let count = psdus.len().min(outcomes.len());
+
+        if count < psdus.len() {
+            warn!(
+                "802.15.4: Burst TX of {} frames, but only {} outcome slots",
+                psdus.len(),
+                outcomes.len()
+            );
+        }
+
+        let mut header = MacHeader::new();
+        let mut ack_header = MacHeader::new();
+        let mut ack_psdu_buf = [0_u8; OT_RADIO_FRAME_MAX_SIZE as _];
+
+        for (index, (psdu, outcome)) in psdus.iter().zip(outcomes.iter_mut()).enumerate() {
+            let needs_ack = header.load(psdu).is_some() && header.needs_ack();
+
+            trace!(
+                "802.15.4: Burst TX frame {}/{}, seq {}",
+                index + 1,
+                count,
+                header.seq
+            );
+
+            let result = self
+                .transmit(psdu, cca, needs_ack.then_some(&mut ack_psdu_buf[..]))
+                .await;
+
+            *outcome = match result {
+                Ok(Some(ack_meta)) => {
+                    let ack_psdu = &ack_psdu_buf[..ack_meta.len];
+
+                    if ack_header.load(ack_psdu).is_some() && ack_header.ack_for(header.seq) {
+                        Ok(Some(ack_meta))
+                    } else {
+                        trace!(
+                            "802.15.4: Burst TX frame seq {}, disregarding unrelated ACK: {}",
+                            header.seq,
+                            Bytes(ack_psdu)
+                        );
+
+                        Ok(None)
+                    }
+                }
+                other => other,
+            };
+        }
+
+        count
+    }
+
+    /// Return the receive statistics gathered since the radio was created
+    /// (or since the last [`Self::reset_stats`]): `(rx_frames, rx_bytes, rx_dropped)`.
+    ///
+    /// - `rx_frames`: Frames successfully returned by [`Radio::receive`]
+    /// - `rx_bytes`: Total PSDU bytes of these frames
+    /// - `rx_dropped`: Frames delivered by esp-radio but rejected by `receive` (malformed,
+    ///   or too large for the provided buffer). Frames dropped by esp-radio itself
+    ///   because its receive queue was full are not visible here.
+    ///
+    /// All counters wrap around on overflow.
+    pub fn rx_stats(&self) -> (u32, u32, u32) {
+        (
+            self.stats.rx_frames,
+            self.stats.rx_bytes,
+            self.stats.rx_dropped,
+        )
+    }
+
+    /// Return the transmit statistics gathered since the radio was created
+    /// (or since the last [`Self::reset_stats`]): `(tx_frames, tx_failures)`.
+    ///
+    /// - `tx_frames`: Frames reported as transmitted by esp-radio
+    /// - `tx_failures`: Frames reported as failed by esp-radio (e.g. CCA failure or no ACK)
+    ///
+    /// All counters wrap around on overflow.
+    pub fn tx_stats(&self) -> (u32, u32) {
+        (self.stats.tx_frames, self.stats.tx_failures)
+    }
+
+    /// Return the number of failed transmissions per error kind (see [`Self::TX_FAILURE_KINDS`]),
+    /// since the radio was created (or since the last [`Self::reset_stats`]).
+    ///
+    /// Note that esp-radio reports a CCA failure and a missing ACK in the same way, so both
+    /// are counted as [`RadioErrorKind::TxFailed`]; congestion and connectivity issues can only
+    /// be told apart by e.g. comparing with transmissions without an ACK request.
+    ///
+    /// All counters wrap around on overflow.
+    pub fn tx_failure_breakdown(
+        &self,
+    ) -> [(RadioErrorKind, u32); EspRadio::TX_FAILURE_KINDS.len()] {
+        let mut breakdown = [(RadioErrorKind::Other, 0); EspRadio::TX_FAILURE_KINDS.len()];
+
+        for (entry, (kind, count)) in breakdown.iter_mut().zip(
+            Self::TX_FAILURE_KINDS
+                .iter()
+                .zip(self.stats.tx_failures_by_kind.iter()),
+        ) {
+            *entry = (*kind, *count);
+        }
+
+        breakdown
+    }
+
+    /// Reset the per-error-kind transmit failure counters returned by
+    /// [`Self::tx_failure_breakdown`], leaving all other statistics intact.
+    pub fn reset_tx_failure_breakdown(&mut self) {
+        self.stats.tx_failures_by_kind = [0; EspRadio::TX_FAILURE_KINDS.len()];
+    }
+
+    /// Return the number of frames dropped in promiscuous mode because of the limit set with
+    /// [`Self::with_promiscuous_rate_limit`], since the radio was created
+    /// (or since the last [`Self::reset_stats`]).
+    ///
+    /// The counter wraps around on overflow.
+    pub fn rx_rate_limited(&self) -> u32 {
+        self.stats.rx_rate_limited
+    }
+
+    /// Reset the receive and transmit statistics.
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    /// Return the number of receptions which were preempted by a transmission, since the radio
+    /// was created (or since the last [`Self::reset_stats`]).
+    ///
+    /// A [`Radio::transmit`] call while a [`Radio::receive`] is in progress (i.e. after the future
+    /// of the latter was dropped, e.g. by a `select` on a new TX request) aborts the reception and
+    /// transmits right away; it is never deferred. The frames already received by then stay queued
+    /// in esp-radio and are returned by the next [`Radio::receive`] call, so only a frame which is
+    /// on the air while the radio transmits is lost, as the radio is half-duplex.
+    ///
+    /// The counter wraps around on overflow.
+    pub fn rx_preempted(&self) -> u32 {
+        self.stats.rx_preempted
+    }
+
+    /// Return the number of received frames which the radio hardware reported as longer than
+    /// the esp-radio receive buffer (i.e. with a PHY length byte above 128), since the radio was
+    /// created (or since the last [`Self::reset_stats`]).
+    ///
+    /// Such a frame can only be the result of a PHY-level anomaly (e.g. a corrupted PHY header
+    /// on a noisy channel, or a non-802.15.4 O-QPSK transmitter), as IEEE 802.15.4 frames are
+    /// at most 127 bytes. By default, [`Radio::receive`] still delivers such a frame, with its
+    /// length taken from the lower 7 bits of the length byte; with
+    /// [`Self::with_strict_rx_length`], it fails with [`RadioErrorKind::InvalidFrame`]
+    /// instead, and the frame is counted as dropped in [`Self::rx_stats`].
+    /// Frames which are valid but do not fit in the buffer passed to `receive` are not counted here.
+    ///
+    /// The counter wraps around on overflow.
+    pub fn rx_hardware_overflows(&self) -> u32 {
+        self.stats.rx_hardware_overflows
+    }
+
+    /// Return the receive-path latency measured since the radio was created
+    /// (or since the last [`Self::reset_stats`]). See [`RxLatency`].
+    ///
+    /// Comparing this with the inter-frame gaps of the traffic shows whether the application's
+    /// receive loop keeps up; use it together with [`Self::with_rx_queue_size`] to diagnose
+    /// RX bottlenecks.
+    #[cfg(feature = "rx-latency")]
+    pub fn rx_latency(&self) -> &RxLatency {
+        &self.stats.rx_latency
+    }
+
+    /// Return the number of radio resets done by the liveness check configured with
+    /// [`Self::with_liveness_timeout`], since the radio was created
+    /// (or since the last [`Self::reset_stats`]).
+    ///
+    /// The counter wraps around on overflow.
+    pub fn liveness_resets(&self) -> u32 {
+        self.stats.liveness_resets
+    }
+
+    /// Return the time elapsed since the last radio activity, i.e. since the last frame was
+    /// received or the last transmission completed (successfully or not).
+    ///
+    /// If there was no activity yet, the time since the radio was created (or since the last
+    /// liveness reset, see [`Self::with_liveness_timeout`]) is returned.
+    pub fn time_since_last_activity(&self) -> Duration {
+        Instant::now().saturating_duration_since(self.last_activity)
+    }
+
+    /// Return the range (min, max) of the channels supported by the radio.
+    ///
+    /// [`Radio::set_config`] rejects a configuration with a channel outside of this range
+    /// with [`RadioErrorKind::ConfigUnsupported`], keeping the current configuration.
+    pub fn channel_range(&self) -> (u8, u8) {
+        (Self::CHANNEL_MIN, Self::CHANNEL_MAX)
+    }
+
+    /// Return the range (min, max) - in dBm - of the CCA ED threshold accepted by the radio.
+    ///
+    /// Note that on the ESP radio the `ed_threshold` of [`Cca`] is interpreted as a signed
+    /// dBm value (i.e. `ed_threshold as i8`). Thresholds outside of this range are clamped
+    /// (with a warning) by [`Radio::set_config`].
+    pub fn cca_threshold_range(&self) -> (i8, i8) {
+        (Self::CCA_THRESHOLD_MIN, Self::CCA_THRESHOLD_MAX)
+    }
+
+    /// Return how long to wait for the ACK of a frame with a PSDU of `psdu_len` bytes,
+    /// measured from the moment the frame transmission starts.
+    ///
+    /// This is the airtime of the frame itself, plus the IEEE 802.15.4 `macAckWaitDuration`
+    /// (`aUnitBackoffPeriod + aTurnaroundTime + phySHRDuration + 6 * phySymbolsPerOctet`).
+    /// For the 2.4 GHz PHY, the latter is 54 symbols, or 864us.
+    ///
+    /// NOTE: This is the nominal value, by which a compliant receiver has sent its ACK, not the
+    /// timeout of the driver: esp-radio waits up to [`Self::DRIVER_ACK_TIMEOUT`] for an ACK
+    /// before reporting a transmission as failed.
+    pub fn expected_ack_wait(&self, psdu_len: usize) -> Duration {
+        Duration::from_micros(
+            (phy::airtime_us(psdu_len as _) + phy::ACK_WAIT_SYMBOLS * phy::SYMBOL_US) as _,
+        )
+    }
+
+    /// Convert the `ed_threshold` of [`Cca`] to the dBm value expected by the radio,
+    /// clamping it to [`Self::cca_threshold_range`].
+    fn cca_threshold(ed_threshold: u8) -> i8 {
+        let threshold = ed_threshold as i8;
+        let clamped = threshold.clamp(Self::CCA_THRESHOLD_MIN, Self::CCA_THRESHOLD_MAX);
+
+        if clamped != threshold {
+            warn!(
+                "CCA ED threshold {} dBm out of range, clamped to {} dBm",
+                threshold, clamped
+            );
+        }
+
+        clamped
+    }
+
+    /// Transmit on the TX channel, returning to the RX channel afterwards.
+    ///
+    /// If the two channels are different, each retune costs a driver reconfiguration plus
+    /// the channel settle time (see [`Config::channel_settle_us`]) - once before the transmission,
+    /// and once after it, before the next reception - during which frames on the RX channel
+    /// are missed.
+    async fn transmit_with(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+        idle_after_tx: bool,
+    ) -> Result<Option<PsduMeta>, RadioErrorKind> {
+        // The refusals are checked before anything else (retuning, waiting for the channel
+        // to settle), so that a refused transmission has no other effect than being counted
+        // as such
+        if self.silent || self.config.monitor_mode {
+            debug!("802.15.4: TX refused, radio silence or monitor mode");
+            self.count_tx_failure(RadioErrorKind::Silenced);
+            return Err(RadioErrorKind::Silenced);
+        }
+
+        if psdu.len() > phy::MAX_PSDU_LEN as usize {
+            warn!("802.15.4: TX refused, frame too long: {} bytes", psdu.len());
+            self.count_tx_failure(RadioErrorKind::InvalidFrame);
+            return Err(RadioErrorKind::InvalidFrame);
+        }
+
+        // Guard against a transmission still in flight (e.g. one whose `transmit` future
+        // was dropped), whose outcome would otherwise be reported as ours
+        if TX_IN_FLIGHT.load(Ordering::SeqCst) {
+            trace!("802.15.4: TX busy");
+            self.count_tx_failure(RadioErrorKind::Busy);
+            return Err(RadioErrorKind::Busy);
+        }
+
+        self.tune(true);
+
+        let result = self
+            .transmit_tuned(psdu, cca, ack_psdu_buf, idle_after_tx)
+            .await;
+
+        self.tune(false);
+
+        if let Err(kind) = result {
+            self.count_tx_failure(kind);
+        }
+
+        result
+    }
+
+    fn count_tx_failure(&mut self, kind: RadioErrorKind) {
+        if let Some(index) = Self::TX_FAILURE_KINDS.iter().position(|k| *k == kind) {
+            let count = &mut self.stats.tx_failures_by_kind[index];
+            *count = count.wrapping_add(1);
+        }
+    }
+
+    async fn transmit_tuned(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+        idle_after_tx: bool,
+    ) -> Result<Option<PsduMeta>, RadioErrorKind> {
+        self.wait_channel_settled().await;
+
+        if self.receiving {
+            // The future of a reception in progress was dropped in favor of this transmission
+            trace!("802.15.4: RX preempted by TX");
+
+            self.stats.rx_preempted = self.stats.rx_preempted.wrapping_add(1);
+            self.receiving = false;
+        }
+
+        if self.idle_after_tx != idle_after_tx {
+            self.idle_after_tx = idle_after_tx;
+            self.update_driver_config();
+        }
+
+        let mut retries = 0;
+
+        let success = loop {
+            TX_SIGNAL.reset();
+
+            trace!(
+                "802.15.4: About to TX {} bytes ch{}",
+                psdu.len(),
+                self.config.effective_tx_channel()
+            );
+
+            self.debug_pulse(DebugEvents::TX_START);
+
+            TX_IN_FLIGHT.store(true, Ordering::SeqCst);
+
+            // esp-radio does not report back whether it did a CCA, however it always does one
+            // (with the configured `cca_mode`) if - and only if - requested
+            TX_CCA.store(cca, Ordering::SeqCst);
+
+            // esp-radio (as of 0.18) always returns `Ok` here, as it does not validate the frame
+            // at all - a frame too large for its TX buffer makes it panic instead, hence
+            // the length check in `transmit_with`
+            let _ = self.driver.transmit_raw(psdu, cca);
+
+            let success = TX_SIGNAL.wait().await;
+
+            self.last_activity = Instant::now();
+
+            if success || retries >= self.tx_glitch_retries {
+                break success;
+            }
+
+            retries += 1;
+
+            trace!(
+                "802.15.4: TX failed, retrying ({}/{})",
+                retries,
+                self.tx_glitch_retries
+            );
+        };
+
+        if success {
+            trace!("802.15.4: TX done");
+
+            self.debug_pulse(DebugEvents::TX_DONE);
+
+            self.stats.tx_frames = self.stats.tx_frames.wrapping_add(1);
+
+            // The ESP radio reports a frame which requested an ACK as transmitted
+            // only once the ACK was received. The driver keeps the last ACK it received,
+            // so for any other frame, that ACK is a stale one
+            let ack_requested = MacHeader::ack_requested(psdu).unwrap_or(false);
+
+            let ack_frame = if ack_requested {
+                self.driver.get_ack_frame()
+            } else {
+                None
+            };
+
+            self.last_tx_outcome = if ack_requested {
+                let frame_pending = ack_frame
+                    .as_ref()
+                    .and_then(|ack_frame| MacHeader::frame_pending(esp_psdu(&ack_frame.data)))
+                    .unwrap_or(true);
+
+                Some(TxOutcome::Acked {
+                    cca_performed: cca,
+                    frame_pending,
+                })
+            } else {
+                Some(TxOutcome::Sent { cca_performed: cca })
+            };
+
+            let Some(ack_frame) = ack_frame.as_ref() else {
+                return Ok(None);
+            };
+
+            // The FCS of the ACK is not checked here: the radio hardware already drops ACKs with
+            // a bad CRC, and esp-radio does not pass the received FCS on (the last two bytes of
+            // the ACK hold its RSSI and LQI instead)
+            let ack_psdu = esp_psdu(&ack_frame.data);
+
+            if ack_psdu.len() < MacHeader::ACK_PSDU_LEN {
+                trace!(
+                    "802.15.4: Malformed ACK frame: {} bytes, {}",
+                    ack_psdu.len(),
+                    Bytes(ack_psdu)
+                );
 
-use crate::fmt::Bytes;
-use crate::{Capabilities, Cca, Config, MacCapabilities, PsduMeta, Radio, RadioErrorKind};
+                if !self.lenient_ack {
+                    return Err(RadioErrorKind::RxAckInvalid);
+                }
+            }
 
-pub use esp_radio::ieee802154::Ieee802154;
+            trace!(
+                "802.15.4: ACK: {} on ch{}",
+                Bytes(ack_psdu),
+                ack_frame.channel
+            );
 
-/// The `esp-hal` ESP IEEE 802.15.4 radio.
-pub struct EspRadio<'a> {
-    driver: Ieee802154<'a>,
-    config: Config,
-    rx_queue_size: usize,
-}
+            self.debug_pulse(DebugEvents::ACK_RECEIVED);
 
-impl<'a> EspRadio<'a> {
-    const DEFAULT_CONFIG: Config = Config::new();
+            let Some(ack_psdu_buf) = ack_psdu_buf else {
+                return Ok(None);
+            };
 
-    /// Default esp-radio receive-queue depth (frames buffered before drops).
-    ///
-    /// esp-radio's own default of 10 is too small for OpenThread's RX bursts; 50
-    /// is a safer baseline. Bursty Matter commissioning/SRP may need more — see
-    /// [`EspRadio::with_rx_queue_size`].
-    pub const DEFAULT_RX_QUEUE_SIZE: usize = 50;
+            if ack_psdu.len() > ack_psdu_buf.len() {
+                trace!(
+                    "802.15.4: ACK frame too large for provided buffer: {} bytes",
+                    ack_psdu.len()
+                );
 
-    /// Create a new `EspRadio` instance.
-    pub fn new(ieee802154: Ieee802154<'a>) -> Self {
-        let mut this = Self {
-            driver: ieee802154,
-            config: Self::DEFAULT_CONFIG,
-            rx_queue_size: Self::DEFAULT_RX_QUEUE_SIZE,
+                return Err(RadioErrorKind::BufferTooSmall);
+            }
+
+            ack_psdu_buf[..ack_psdu.len()].copy_from_slice(ack_psdu);
+
+            Ok(Some(PsduMeta {
+                len: ack_psdu.len(),
+                channel: ack_frame.channel,
+                channel_page: 0,
+                rssi: esp_rssi(ack_psdu),
+                timestamp: None,
+            }))
+        } else {
+            trace!("802.15.4: TX failed");
+
+            self.stats.tx_failures = self.stats.tx_failures.wrapping_add(1);
+            self.last_tx_outcome = Some(TxOutcome::Failed);
+
+            // Report as a failure so OpenThread SubMac retries
+            Err(RadioErrorKind::TxFailed)
+        }
+    }
+
+    /// Wait for the driver to signal a received frame, resetting the radio on the way
+    /// if the liveness timeout (if any) expires first.
+    async fn wait_rx_signal(&mut self) {
+        let Some(timeout) = self.liveness_timeout else {
+            RX_SIGNAL.wait().await;
+            return;
         };
 
-        this.driver.set_rx_available_callback_fn(Self::rx_callback);
-        this.driver.set_tx_done_callback_fn(Self::tx_done_callback);
-        this.driver
-            .set_tx_failed_callback_fn(Self::tx_failed_callback);
+        if with_deadline(self.last_activity + timeout, RX_SIGNAL.wait())
+            .await
+            .is_err()
+        {
+            warn!(
+                "802.15.4: No radio activity for {}ms, resetting",
+                timeout.as_millis()
+            );
 
-        this.update_driver_config();
+            self.stats.liveness_resets = self.stats.liveness_resets.wrapping_add(1);
 
-        this
+            self.reset();
+            self.last_activity = Instant::now();
+        }
     }
 
-    /// Set the esp-radio receive-queue depth: frames buffered before further
-    /// incoming frames are dropped (logged as `Receive queue full`).
+    /// Pop the next received frame from the driver queue, if any,
+    /// dropping the frames over the promiscuous-mode rate limit.
+    fn pop_received(&mut self) -> Option<RawReceived> {
+        while let Some(frame) = self.driver.raw_received() {
+            self.last_activity = Instant::now();
+
+            if self.config.is_promiscuous() {
+                if let Some(rate_limit) = self.promiscuous_rate_limit.as_mut() {
+                    if !rate_limit.admit(Instant::now()) {
+                        self.stats.rx_rate_limited = self.stats.rx_rate_limited.wrapping_add(1);
+                        continue;
+                    }
+                }
+            }
+
+            return Some(frame);
+        }
+
+        None
+    }
+
+    fn process_received(
+        &mut self,
+        raw: RawReceived,
+        psdu_buf: &mut [u8],
+    ) -> Result<PsduMeta, RadioErrorKind> {
+        self.last_raw = Some(raw);
+
+        if raw.data.len() < 1 {
+            // Must have at least 1 byte for PSDU
+            self.stats.rx_dropped = self.stats.rx_dropped.wrapping_add(1);
+            return Err(RadioErrorKind::Other);
+        }
+
+        if raw.data[0] as usize > raw.data.len() - 1 {
+            // The hardware wrote a length beyond the end of the receive buffer;
+            // esp-radio only copied the part which fits
+            warn!(
+                "802.15.4: Received frame larger than the radio buffer: length byte {}",
+                raw.data[0]
+            );
+            self.stats.rx_hardware_overflows = self.stats.rx_hardware_overflows.wrapping_add(1);
+        }
+
+        if self.strict_rx_length
+            && raw.data[0] as usize > (raw.data.len() - 1).min(phy::MAX_PSDU_LEN as _)
+        {
+            trace!(
+                "802.15.4: Received frame with invalid length byte {}",
+                raw.data[0]
+            );
+            self.stats.rx_dropped = self.stats.rx_dropped.wrapping_add(1);
+            return Err(RadioErrorKind::InvalidFrame);
+        }
+
+        let psdu = esp_psdu(&raw.data);
+        let psdu_len = psdu.len();
+        if psdu_len > psdu_buf.len() {
+            // PSDU length is larger than the provided buffer
+            trace!(
+                "802.15.4: Received frame too large for provided buffer: {} bytes",
+                psdu_len
+            );
+            self.stats.rx_dropped = self.stats.rx_dropped.wrapping_add(1);
+            return Err(RadioErrorKind::Other);
+        }
+
+        psdu_buf[..psdu_len].copy_from_slice(psdu);
+
+        if !self.config.is_promiscuous() {
+            let mut header = MacHeader::new();
+
+            if header.load(&psdu_buf[..psdu_len]).is_some() && header.needs_ack() {
+                self.last_ack_seq = Some(header.seq);
+            }
+        }
+
+        let rssi = esp_rssi(&psdu_buf[..psdu_len]);
+
+        trace!(
+            "802.15.4: RX {} bytes ch{} rssi={:?}",
+            psdu_len,
+            raw.channel,
+            rssi
+        );
+
+        self.stats.rx_frames = self.stats.rx_frames.wrapping_add(1);
+        self.stats.rx_bytes = self.stats.rx_bytes.wrapping_add(psdu_len as u32);
+
+        self.debug_pulse(DebugEvents::RX_FRAME);
+
+        Ok(PsduMeta {
+            len: psdu_len,
+            channel: raw.channel,
+            // The ESP radio only supports the 2.4 GHz O-QPSK PHY, i.e. channel page 0
+            channel_page: 0,
+            rssi,
+            timestamp: None,
+        })
+    }
+
+    /// Tune the radio to `channel` for an energy scan, returning the configuration to restore
+    /// with [`Self::energy_scan_end`] once the scan is over.
+    fn energy_scan_begin(&mut self, channel: u8) -> Config {
+        let config = self.config.clone();
+
+        self.config.channel = channel;
+        self.config.tx_channel = None;
+        self.config.rx_channel = None;
+
+        self.update_driver_config();
+        self.start_channel_settle();
+
+        config
+    }
+
+    /// Start receiving on the scanned channel, once the radio settled on it.
     ///
-    /// Raising the default ([`Self::DEFAULT_RX_QUEUE_SIZE`], e.g. to 200) gives
-    /// headroom when the OpenThread consumer stalls under bursty load (e.g.
-    /// crypto during commissioning). Each queued frame is a heap buffer of
-    /// ~130 bytes, allocated on demand and not freed until reset, so the depth
-    /// caps the worst-case RX heap (e.g. 200 ≈ 26 KB).
-    #[must_use]
-    pub fn with_rx_queue_size(mut self, rx_queue_size: usize) -> Self {
-        self.rx_queue_size = rx_queue_size;
+    /// The frames queued until then (i.e. mostly received on the configured channel) are
+    /// discarded, so that they are not counted as frames of the scanned channel.
+    fn energy_scan_listen(&mut self) {
+        while self.driver.raw_received().is_some() {
+            self.stats.rx_dropped = self.stats.rx_dropped.wrapping_add(1);
+        }
+
+        RX_SIGNAL.reset();
+
+        self.start_receive();
+    }
+
+    /// Account all frames queued by the driver in the result of an energy scan.
+    ///
+    /// Unlike [`Self::process_received`], this leaves the RX statistics and the ACK state alone.
+    fn energy_scan_poll(&mut self, result: &mut EnergyScanResult) {
+        while let Some(raw) = self.driver.raw_received() {
+            self.last_activity = Instant::now();
+
+            result.frames_seen = result.frames_seen.wrapping_add(1);
+
+            if let Some(rssi) = esp_rssi(esp_psdu(&raw.data)) {
+                result.peak_rssi = Some(result.peak_rssi.map_or(rssi, |peak| peak.max(rssi)));
+            }
+        }
+    }
+
+    /// Restore the configuration returned by [`Self::energy_scan_begin`].
+    fn energy_scan_end(&mut self, config: Config) {
+        self.config = config;
+
         self.update_driver_config();
-        self
+        self.start_channel_settle();
+    }
+
+    /// Start the settle time of the radio after a channel change.
+    fn start_channel_settle(&mut self) {
+        let settle_us = self
+            .config
+            .channel_settle_us
+            .unwrap_or(Self::DEFAULT_CHANNEL_SETTLE_US);
+
+        self.channel_settled_at = Some(Instant::now() + Duration::from_micros(settle_us as _));
+    }
+
+    /// Tune the radio to the TX channel (`tx = true`) or to the RX channel (`tx = false`),
+    /// if these are different (see [`Config::tx_channel`] and [`Config::rx_channel`]).
+    fn tune(&mut self, tx: bool) {
+        if self.tx_tuned != tx {
+            self.tx_tuned = tx;
+
+            if self.config.effective_tx_channel() != self.config.effective_rx_channel() {
+                trace!(
+                    "802.15.4: Retuning to the {} channel",
+                    if tx { "TX" } else { "RX" }
+                );
+
+                self.update_driver_config();
+                self.start_channel_settle();
+            }
+        }
+    }
+
+    /// Wait for the radio to settle after a channel change, if necessary.
+    async fn wait_channel_settled(&mut self) {
+        if let Some(settled_at) = self.channel_settled_at {
+            if settled_at > Instant::now() {
+                trace!("802.15.4: Waiting for the channel to settle");
+                Timer::at(settled_at).await;
+            }
+
+            self.channel_settled_at = None;
+        }
+    }
+
+    fn start_receive(&mut self) {
+        // Starting to receive aborts the transmission in flight, if any,
+        // without reporting its outcome
+        self.driver.start_receive();
+        TX_IN_FLIGHT.store(false, Ordering::SeqCst);
+    }
+
+    fn debug_pulse(&mut self, event: DebugEvents) {
+        if let Some(debug_gpio) = self.debug_gpio.as_mut() {
+            if debug_gpio.events.contains(event) {
+                (debug_gpio.pin)(true);
+                (debug_gpio.pin)(false);
+            }
+        }
     }
 
     fn update_driver_config(&mut self) {
         let config = &self.config;
 
         let esp_config = EspConfig {
-            auto_ack_tx: true,
+            auto_ack_tx: !self.silent && !config.monitor_mode,
             auto_ack_rx: true,
-            enhance_ack_tx: true,
-            promiscuous: config.promiscuous,
+            enhance_ack_tx: self.enhanced_ack && !self.silent && !config.monitor_mode,
+            promiscuous: config.is_promiscuous(),
             coordinator: false,
-            rx_when_idle: config.rx_when_idle,
-            txpower: config.power,
-            channel: config.channel,
+            rx_when_idle: config.rx_when_idle && !self.idle_after_tx,
+            txpower: match config
+                .region
+                .and_then(|region| self.regulatory_max_power(config.effective_tx_channel(), region))
+            {
+                Some(max_power) => config.power.min(max_power),
+                None => config.power,
+            },
+            channel: if self.tx_tuned {
+                config.effective_tx_channel()
+            } else {
+                config.effective_rx_channel()
+            },
             cca_threshold: match config.cca {
                 Cca::Carrier => 0,
-                Cca::Ed { ed_threshold } => ed_threshold as _,
-                Cca::CarrierAndEd { ed_threshold } => ed_threshold as _,
-                Cca::CarrierOrEd { ed_threshold } => ed_threshold as _,
+                Cca::Ed { ed_threshold } => Self::cca_threshold(ed_threshold),
+                Cca::CarrierAndEd { ed_threshold } => Self::cca_threshold(ed_threshold),
+                Cca::CarrierOrEd { ed_threshold } => Self::cca_threshold(ed_threshold),
             },
             cca_mode: match config.cca {
                 Cca::Carrier => esp_radio::ieee802154::CcaMode::Carrier,
@@ -99,11 +1404,24 @@ impl<'a> EspRadio<'a> {
     }
 
     fn tx_done_callback() {
+        TX_IN_FLIGHT.store(false, Ordering::SeqCst);
         TX_SIGNAL.signal(true); // success
+        Self::notify_tx_complete(TxOutcome::Sent {
+            cca_performed: TX_CCA.load(Ordering::SeqCst),
+        });
     }
 
     fn tx_failed_callback() {
+        TX_IN_FLIGHT.store(false, Ordering::SeqCst);
         TX_SIGNAL.signal(false); // failure
+        Self::notify_tx_complete(TxOutcome::Failed);
+    }
+
+    fn notify_tx_complete(outcome: TxOutcome) {
+        // Do not hold the lock while calling the user callback
+        if let Some(callback) = TX_COMPLETE_CALLBACK.lock(Cell::get) {
+            callback(outcome);
+        }
     }
 }
 
@@ -119,141 +1437,306 @@ impl Radio for EspRadio<'_> {
 
     async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error> {
         if self.config != *config {
+            // Values above the limits are clamped instead: the CCA ED threshold by
+            // `cca_threshold`, and the TX power by esp-radio itself
+            for channel in [
+                config.channel,
+                config.effective_tx_channel(),
+                config.effective_rx_channel(),
+            ] {
+                if !(Self::CHANNEL_MIN..=Self::CHANNEL_MAX).contains(&channel) {
+                    warn!("802.15.4: Unsupported channel {}", channel);
+                    return Err(RadioErrorKind::ConfigUnsupported);
+                }
+            }
+
+            // esp-radio only supports beaconless networks
+            if !config.is_superframe_valid() || config.beacon_order != Config::NON_BEACON_ORDER {
+                warn!(
+                    "802.15.4: Unsupported beacon order {} (superframe order {})",
+                    config.beacon_order, config.superframe_order
+                );
+                return Err(RadioErrorKind::ConfigUnsupported);
+            }
+
             debug!("Setting radio config: {:?}", config);
 
+            let retuned = if self.tx_tuned {
+                self.config.effective_tx_channel() != config.effective_tx_channel()
+            } else {
+                self.config.effective_rx_channel() != config.effective_rx_channel()
+            };
+
             self.config = config.clone();
             self.update_driver_config();
+
+            if retuned {
+                self.start_channel_settle();
+            }
         }
 
         Ok(())
     }
 
+    fn is_promiscuous(&self) -> bool {
+        self.config.is_promiscuous()
+    }
+
+    fn sensitivity_dbm(&self) -> i8 {
+        // A conservative figure for the IEEE 802.15.4 receivers of the ESP32-C6 and ESP32-H2;
+        // see the "RF Characteristics" section of the respective datasheet.
+        //
+        // Note that ESP-IDF reports a much more optimistic -120 dBm to OpenThread.
+        -95
+    }
+
+    fn radio_time_now(&self) -> u64 {
+        // NOTE: esp-radio does not expose the radio timer, so the `embassy-time` time is the
+        // only time base available (and the one used for all `EspRadio` timing)
+        Instant::now().as_micros()
+    }
+
+    fn power_step_db(&self) -> u8 {
+        // esp-radio maps the requested dBm power to a PA power index in 3 dB steps, starting
+        // from the lowest supported power (-15 dBm on the ESP32-C6, -24 dBm on the ESP32-H2),
+        // rounding down; see `ieee802154_txpower_convert` in esp-radio (and ESP-IDF)
+        3
+    }
+
+    fn supported_security_levels(&self) -> SecurityLevels {
+        // The AES-CCM* engine of the ESP 802.15.4 radio can do all security levels,
+        // however esp-radio does not expose it (no way to set the keys or to enable the
+        // TX security), so MAC security is left to `OpenThread`
+        SecurityLevels::empty()
+    }
+
+    fn tx_latency(&self) -> Duration {
+        // Not measured on the hardware, but derived from the IEEE 802.15.4 timings the radio
+        // follows: as it is receiving when idle, it needs `aTurnaroundTime` to switch to TX.
+        // A CCA adds `aCcaTime` (8 symbols, 128us) before that. On top of this comes the software
+        // path from `transmit` to the TX command of esp-radio (copying the frame, a critical
+        // section), which is a few tens of microseconds and varies with the CPU load and
+        // interrupt latency
+        Duration::from_micros((phy::TURNAROUND_SYMBOLS * phy::SYMBOL_US) as _)
+    }
+
+    fn channel_bandwidth_khz(&self) -> u32 {
+        // The ESP radio only supports the 2.4 GHz O-QPSK PHY, i.e. 2 MHz wide channels 11 to 26,
+        // centered at 2405 to 2480 MHz (as returned by the default `channel_to_frequency`)
+        2000
+    }
+
+    fn max_frame_rate(&self) -> (u32, u32) {
+        // Theoretical, not measured: for back-to-back maximum-size frames which request an ACK
+        // (as OpenThread unicasts do), with the standard timings the ESP radio follows (see
+        // `phy::max_frame_rate`); i.e. about 173 TX and 183 RX frames per second. The software
+        // overhead (copying the frame, the esp-radio callbacks) is not accounted for, as it
+        // depends on the CPU load
+        phy::max_frame_rate(true)
+    }
+
     async fn transmit(
         &mut self,
         psdu: &[u8],
         cca: bool,
         ack_psdu_buf: Option<&mut [u8]>,
     ) -> Result<Option<PsduMeta>, Self::Error> {
-        TX_SIGNAL.reset();
+        self.transmit_with(psdu, cca, ack_psdu_buf, false).await
+    }
+
+    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+        RX_SIGNAL.reset();
+
+        // In case a transmission future was dropped while tuned to the TX channel
+        self.tune(false);
+        self.wait_channel_settled().await;
 
         trace!(
-            "802.15.4: About to TX {} bytes ch{}",
-            psdu.len(),
-            self.config.channel
+            "802.15.4: About to RX on ch{}",
+            self.config.effective_rx_channel()
         );
 
-        self.driver
-            .transmit_raw(psdu, cca)
-            .map_err(|_| RadioErrorKind::Other)?;
+        self.start_receive();
+        self.receiving = true;
 
-        let success = TX_SIGNAL.wait().await;
+        #[cfg(feature = "rx-latency")]
+        let mut signaled_at = Instant::now();
 
-        if success {
-            trace!("802.15.4: TX done");
+        let raw = loop {
+            if let Some(frame) = self.pop_received() {
+                break frame;
+            }
 
-            if let Some(ack_psdu_buf) = ack_psdu_buf {
-                // After tx_done signal received, get the ACK frame:
-                if let Some(ack_frame) = self.driver.get_ack_frame() {
-                    if ack_frame.data.len() >= 1 {
-                        // Must have at least 1 byte for PSDU
-                        let ack_psdu_len =
-                            (ack_frame.data.len() - 1).min((ack_frame.data[0] & 0x7f) as usize);
-
-                        if ack_psdu_len <= ack_psdu_buf.len() {
-                            ack_psdu_buf[..ack_psdu_len]
-                                .copy_from_slice(&ack_frame.data[1..][..ack_psdu_len]);
-
-                            trace!(
-                                "802.15.4: ACK: {} on ch{}",
-                                Bytes(&ack_psdu_buf[..ack_psdu_len]),
-                                ack_frame.channel
-                            );
-
-                            // Only read RSSI if there is at least one byte after the PSDU.
-                            let rssi = if ack_frame.data.len() > 1 + ack_psdu_len {
-                                Some(ack_frame.data[1..][ack_psdu_len] as i8)
-                            } else {
-                                None
-                            };
-
-                            return Ok(Some(PsduMeta {
-                                len: ack_psdu_len,
-                                channel: ack_frame.channel,
-                                rssi,
-                            }));
-                        } else {
-                            trace!(
-                                "802.15.4: ACK frame too large for provided buffer: {} bytes",
-                                ack_psdu_len
-                            );
-                        }
-                    }
-                }
+            self.wait_rx_signal().await;
+
+            #[cfg(feature = "rx-latency")]
+            {
+                signaled_at = Instant::now();
             }
+        };
 
-            Ok(None)
-        } else {
-            trace!("802.15.4: TX failed");
+        self.receiving = false;
 
-            // Report as a failure so OpenThread SubMac retries
-            Err(RadioErrorKind::TxFailed)
+        let result = self.process_received(raw, psdu_buf);
+
+        #[cfg(feature = "rx-latency")]
+        if result.is_ok() {
+            self.stats.rx_latency.record(signaled_at.elapsed());
         }
+
+        result
     }
+}
 
-    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
-        RX_SIGNAL.reset();
+/// Return the PSDU of a received frame or ACK, given the raw esp-radio receive buffer
+/// (the PHY length byte followed by the PSDU).
+///
+/// A length beyond the end of the buffer is clamped to it.
+fn esp_psdu(data: &[u8]) -> &[u8] {
+    let psdu_len = (data.len() - 1).min((data[0] & 0x7f) as usize);
 
-        trace!("802.15.4: About to RX on ch{}", self.config.channel);
+    &data[1..][..psdu_len]
+}
 
-        self.driver.start_receive();
+/// Return the RSSI (in dBm) of a received frame or ACK with the given PSDU.
+///
+/// The ESP radio does not write the FCS of a received frame to the receive buffer. Instead, the
+/// first of the two FCS bytes holds the RSSI of the frame, as a signed dBm value with a 1 dB
+/// resolution (i.e. `0xc4` is -60 dBm), and the second one its LQI. This is also where
+/// esp-radio itself reads the RSSI from.
+///
+/// Returns `None` if the PSDU is too short to contain the FCS bytes.
+fn esp_rssi(psdu: &[u8]) -> Option<i8> {
+    (psdu.len() >= 2).then(|| psdu[psdu.len() - 2] as i8)
+}
 
-        let raw = loop {
-            if let Some(frame) = self.driver.raw_received() {
-                break frame;
-            }
+/// The debug GPIO set with [`EspRadio::set_debug_gpio`].
+struct DebugGpio<'a> {
+    pin: &'a mut (dyn FnMut(bool) + Send),
+    events: DebugEvents,
+}
 
-            RX_SIGNAL.wait().await;
-        };
+/// A simple frames-per-second limiter, with a fixed one second window.
+struct RateLimit {
+    max_frames: u32,
+    window_start: Instant,
+    window_frames: u32,
+}
 
-        if raw.data.len() < 1 {
-            // Must have at least 1 byte for PSDU
-            return Err(RadioErrorKind::Other);
-        }
+impl RateLimit {
+    const WINDOW: Duration = Duration::from_secs(1);
 
-        let psdu_len = (raw.data.len() - 1).min((raw.data[0] & 0x7f) as usize);
-        if psdu_len > psdu_buf.len() {
-            // PSDU length is larger than the provided buffer
-            trace!(
-                "802.15.4: Received frame too large for provided buffer: {} bytes",
-                psdu_len
-            );
-            return Err(RadioErrorKind::Other);
+    const fn new(max_frames: u32) -> Self {
+        Self {
+            max_frames,
+            window_start: Instant::from_ticks(0),
+            window_frames: 0,
         }
+    }
 
-        psdu_buf[..psdu_len].copy_from_slice(&raw.data[1..][..psdu_len]);
+    /// Return `true` if a frame arriving at `now` is within the limit.
+    fn admit(&mut self, now: Instant) -> bool {
+        if now >= self.window_start + Self::WINDOW {
+            self.window_start = now;
+            self.window_frames = 0;
+        }
 
-        // Only read RSSI if there is at least one byte after the PSDU.
-        let rssi = if raw.data.len() > 1 + psdu_len {
-            Some(raw.data[1..][psdu_len] as i8)
+        if self.window_frames < self.max_frames {
+            self.window_frames += 1;
+            true
         } else {
-            None
-        };
+            false
+        }
+    }
+}
 
-        trace!(
-            "802.15.4: RX {} bytes ch{} rssi={:?}",
-            psdu_len,
-            raw.channel,
-            rssi
-        );
+/// Built-in `EspRadio` RX/TX counters.
+#[derive(Debug, Default, Clone)]
+struct Stats {
+    rx_frames: u32,
+    rx_bytes: u32,
+    rx_dropped: u32,
+    rx_rate_limited: u32,
+    tx_frames: u32,
+    tx_failures: u32,
+    liveness_resets: u32,
+    rx_preempted: u32,
+    rx_hardware_overflows: u32,
+    tx_failures_by_kind: [u32; EspRadio::TX_FAILURE_KINDS.len()],
+    #[cfg(feature = "rx-latency")]
+    rx_latency: RxLatency,
+}
 
-        Ok(PsduMeta {
-            len: psdu_len,
-            channel: raw.channel,
-            rssi,
-        })
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            rx_frames: 0,
+            rx_bytes: 0,
+            rx_dropped: 0,
+            rx_rate_limited: 0,
+            tx_frames: 0,
+            tx_failures: 0,
+            liveness_resets: 0,
+            rx_preempted: 0,
+            rx_hardware_overflows: 0,
+            tx_failures_by_kind: [0; EspRadio::TX_FAILURE_KINDS.len()],
+            #[cfg(feature = "rx-latency")]
+            rx_latency: RxLatency::new(),
+        }
+    }
+}
+
+/// The latency of the [`Radio::receive`] path of [`EspRadio`]: the time from the driver signaling
+/// a received frame to `receive` returning it, as measured with `embassy-time`.
+///
+/// If a frame was already queued when `receive` was called, the latency is measured from the call
+/// instead. Only frames successfully returned by `receive` are measured.
+///
+/// See [`EspRadio::rx_latency`].
+#[cfg(feature = "rx-latency")]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxLatency {
+    /// The latency of the last frame
+    pub last: Duration,
+    /// The largest latency seen
+    pub max: Duration,
+    /// The sum of the latencies of all measured frames
+    pub total: Duration,
+    /// The number of measured frames (wraps around on overflow)
+    pub frames: u32,
+}
+
+#[cfg(feature = "rx-latency")]
+impl RxLatency {
+    const fn new() -> Self {
+        Self {
+            last: Duration::from_ticks(0),
+            max: Duration::from_ticks(0),
+            total: Duration::from_ticks(0),
+            frames: 0,
+        }
+    }
+
+    /// Return the average latency of the measured frames, or `None` if no frame was measured yet.
+    pub fn average(&self) -> Option<Duration> {
+        (self.frames > 0).then(|| self.total / self.frames)
+    }
+
+    fn record(&mut self, latency: Duration) {
+        self.last = latency;
+        self.max = self.max.max(latency);
+        self.total += latency;
+        self.frames = self.frames.wrapping_add(1);
     }
 }
 
 // Esp chips have a single radio, so having statics for these is OK
 static TX_SIGNAL: Signal<CriticalSectionRawMutex, bool> = Signal::new();
 static RX_SIGNAL: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+static TX_IN_FLIGHT: AtomicBool = AtomicBool::new(false);
+static TX_CCA: AtomicBool = AtomicBool::new(false);
+static TX_COMPLETE_CALLBACK: Mutex<CriticalSectionRawMutex, Cell<Option<TxCompleteCallback>>> =
+    Mutex::new(Cell::new(None));
+
+type TxCompleteCallback = fn(TxOutcome);
@@ -1,6 +1,6 @@
 //! A safe API for OpenThread (`openthread-sys`)
 
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![allow(unknown_lints)]
 #![allow(async_fn_in_trait)]
 #![allow(clippy::uninlined_format_args)]
@@ -62,6 +62,8 @@ mod platform;
 mod radio;
 #[cfg(feature = "rcp")]
 pub mod rcp;
+#[cfg(feature = "remote")]
+pub mod remote;
 mod scan;
 mod settings;
 mod signal;
@@ -973,6 +975,7 @@ impl<'a> OpenThread<'a> {
     {
         // Fetch the radio capabilities from the driver
         self.activate().state().ot.radio_caps = R::CAPS.bits();
+        self.activate().state().ot.radio_sensitivity = radio.sensitivity_dbm();
 
         /// Fill the OpenThread frame structure based on the PSDU data returned by the radio
         fn fill_frame(
@@ -1005,7 +1008,10 @@ impl<'a> OpenThread<'a> {
             frame.mChannel = psdu_meta.channel;
             frame.mInfo.mRxInfo.mRssi = rssi;
             frame.mInfo.mRxInfo.mLqi = rssi_to_lqi(rssi);
-            frame.mInfo.mRxInfo.mTimestamp = Instant::now().as_micros(); // TODO: Not precise
+            // TODO: Not precise if the radio does not timestamp the frames
+            frame.mInfo.mRxInfo.mTimestamp = psdu_meta
+                .timestamp
+                .unwrap_or_else(|| Instant::now().as_micros());
         }
 
         let mut radio = MacRadio::new(radio, timer);
@@ -1393,6 +1399,7 @@ impl OtResources {
             radio: Signal::new(),
             radio_conf: Config::new(),
             radio_caps: OT_RADIO_CAPS_ACK_TIMEOUT as otRadioCaps,
+            radio_sensitivity: -100,
             pending_rx_when_idle: None,
         }));
 
@@ -1959,9 +1966,8 @@ impl<'a> OtContext<'a> {
         rssi
     }
 
-    // from https://github.com/espressif/esp-idf/blob/release/v5.3/components/openthread/src/port/esp_openthread_radio.c#L35
     fn plat_radio_receive_sensititivy(&mut self) -> i8 {
-        let sens = 0; // TODO
+        let sens = self.state().ot.radio_sensitivity;
         trace!(
             "Plat radio receive sensitivity callback, sensitivity: {}",
             sens
@@ -2269,6 +2275,9 @@ struct OtState<'a> {
     /// Radio capabilities reported to OpenThread via otPlatRadioGetCaps.
     /// Fetched from the actual radio trait in the `OpenThread::run` API.
     radio_caps: otRadioCaps,
+    /// Radio receive sensitivity reported to OpenThread via otPlatRadioGetReceiveSensitivity.
+    /// Fetched from the actual radio trait in the `OpenThread::run` API.
+    radio_sensitivity: i8,
     /// Deferred rx_when_idle value from set_link_mode called before device connects.
     /// Applied automatically via plat_changed when the device role becomes connected.
     pending_rx_when_idle: Option<bool>,
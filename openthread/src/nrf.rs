@@ -48,8 +48,16 @@ impl<'a> NrfRadio<'a> {
             Cca::CarrierAndEd { ed_threshold } => RadioCca::EnergyDetection { ed_threshold },
             Cca::CarrierOrEd { ed_threshold } => RadioCca::EnergyDetection { ed_threshold },
         });
+        let power = match config
+            .region
+            .and_then(|region| self.regulatory_max_power(config.effective_tx_channel(), region))
+        {
+            Some(max_power) => config.power.min(max_power),
+            None => config.power,
+        };
+
         self.driver
-            .set_transmission_power(Self::clamp_tx_power(config.power));
+            .set_transmission_power(Self::clamp_tx_power(power));
     }
 
     /// Snap a requested transmit power (in dBm) to a value the nRF radio's
@@ -104,6 +112,21 @@ impl Radio for NrfRadio<'_> {
         Ok(())
     }
 
+    fn is_promiscuous(&self) -> bool {
+        self.config.is_promiscuous()
+    }
+
+    fn sensitivity_dbm(&self) -> i8 {
+        // The IEEE 802.15.4 receive sensitivity from the nRF52840 product specification
+        -100
+    }
+
+    fn power_step_db(&self) -> u8 {
+        // The smallest step between the supported levels (see `clamp_tx_power`);
+        // below 2 dBm the levels are 2 to 10 dB apart
+        1
+    }
+
     async fn transmit(
         &mut self,
         psdu: &[u8],
@@ -153,7 +176,9 @@ impl Radio for NrfRadio<'_> {
                 // however, OpenThread wants the PSDU len to include the CRC
                 len: len + 2,
                 channel,
+                channel_page: 0,
                 rssi: Some(rssi),
+                timestamp: None,
             });
         }
     }
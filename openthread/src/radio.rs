@@ -4,7 +4,7 @@
 
 #![allow(clippy::unnecessary_cast)]
 
-use core::cell::Cell;
+use core::cell::{Cell, RefCell, UnsafeCell};
 use core::fmt::Debug;
 use core::future::Future;
 use core::mem::MaybeUninit;
@@ -13,10 +13,12 @@ use core::pin::pin;
 use embassy_futures::select::{select, Either};
 
 use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, RawMutex};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::mutex::{Mutex as AsyncMutex, MutexGuard};
 use embassy_sync::signal::Signal;
 use embassy_sync::zerocopy_channel::{Channel, Receiver, Sender};
 
-use embassy_time::Instant;
+use embassy_time::{with_timeout, Duration, Instant};
 
 use mac::MacHeader;
 use openthread_sys::{
@@ -53,6 +55,20 @@ pub enum RadioErrorKind {
     RxAckTimeout,
     /// Transmitting failed due to invalid ACK received
     RxAckInvalid,
+    /// Transmitting failed because the frame could not be sent before its deadline
+    DeadlineMissed,
+    /// The radio is busy with another (e.g. a previous, cancelled) transmission
+    Busy,
+    /// Transmitting is not possible because the radio is in radio silence mode (or in monitor mode)
+    Silenced,
+    /// The configuration has a value outside of the limits of the radio (e.g. an invalid channel)
+    ConfigUnsupported,
+    /// The frame is malformed, e.g. its length is invalid
+    InvalidFrame,
+    /// The buffer provided by the caller is too small for the received frame (or ACK)
+    BufferTooSmall,
+    /// Transmitting was refused because the transmit rate limit was exceeded
+    RateLimited,
     /// Other radio error
     Other,
 }
@@ -63,12 +79,85 @@ pub trait RadioError: Debug {
     fn kind(&self) -> RadioErrorKind;
 }
 
+impl RadioErrorKind {
+    /// Return `true` if retrying the failed operation (with the same frame, for TX)
+    /// might succeed, or `false` if the error is fatal for the operation, so that
+    /// retrying it would only waste time and airtime.
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            // The frame itself is malformed
+            Self::TxInvalid | Self::InvalidFrame => false,
+            // The frame is worthless after its deadline had passed
+            Self::DeadlineMissed => false,
+            // The radio will not transmit anything until the radio silence is lifted
+            Self::Silenced => false,
+            // The same configuration would be rejected again
+            Self::ConfigUnsupported => false,
+            // The frame would not fit in the same buffer again
+            Self::BufferTooSmall => false,
+            Self::RxInvalid
+            | Self::RxFailed
+            | Self::TxFailed
+            | Self::TxAckFailed
+            | Self::RxAckFailed
+            | Self::TxAckTimeout
+            | Self::RxAckTimeout
+            | Self::RxAckInvalid
+            | Self::Busy
+            | Self::RateLimited
+            | Self::Other => true,
+        }
+    }
+}
+
 impl RadioError for RadioErrorKind {
     fn kind(&self) -> RadioErrorKind {
         *self
     }
 }
 
+/// A regulatory region, for the transmit power limits of the 2.4 GHz band.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Region {
+    /// USA (FCC part 15.247) and the regions following it (e.g. Canada)
+    Fcc,
+    /// Europe (ETSI EN 300 328)
+    Etsi,
+    /// Japan (ARIB STD-T66)
+    Japan,
+}
+
+impl Region {
+    /// Return the maximum transmit power, in dBm, allowed by the region on the given
+    /// 2.4 GHz channel (11..=26), or `None` for any other channel, for which the region
+    /// has no 2.4 GHz limit.
+    ///
+    /// The regulations limit either the conducted power (at the antenna port) or the EIRP
+    /// (including the antenna gain). The values returned are for an antenna gain of 0 dBi,
+    /// where the two are the same; with a higher gain, the EIRP limits must be lowered by it:
+    /// - FCC (47 CFR 15.247(b)(3)): 1 W (30 dBm) conducted output power, for antenna gains of
+    ///   up to 6 dBi, on all channels. Note that on the band-edge channels 25 (2475 MHz) and
+    ///   26 (2480 MHz), the emissions must also stay below the 15.209 limits in the restricted
+    ///   band starting at 2483.5 MHz (15.205). The power this allows depends on the spectrum
+    ///   of the actual radio and antenna, and is therefore not accounted for here
+    /// - ETSI (EN 300 328): 20 dBm EIRP, and a power spectral density of 10 dBm/MHz EIRP,
+    ///   which limits the 2 MHz wide O-QPSK signal to about 10 dBm EIRP on all channels
+    /// - Japan (ARIB STD-T66): a power density of 10 mW/MHz (10 dBm/MHz) antenna power, for
+    ///   antenna gains of up to 2.14 dBi, which likewise means about 10 dBm on all channels
+    ///
+    /// They are meant as a safety net; the limits the final product is certified for take
+    /// precedence, and should be used instead with a [`Radio::regulatory_max_power`] override
+    /// if they are lower.
+    pub const fn max_power(&self, channel: u8) -> Option<i8> {
+        match (self, channel) {
+            (_, 0..=10) | (_, 27..) => None,
+            (Self::Fcc, _) => Some(30),
+            (Self::Etsi, _) | (Self::Japan, _) => Some(10),
+        }
+    }
+}
+
 /// Carrier sense or Energy Detection (ED) mode.
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -142,6 +231,34 @@ bitflags! {
         const FILTER_SHORT_ADDR = 0x10;
         /// Radio supports filtering of PHY frames by their extended address in the MAC payload.
         const FILTER_EXT_ADDR = 0x20;
+        /// Radio supports transmitting and receiving on different channels
+        /// (see [`Config::tx_channel`] and [`Config::rx_channel`]).
+        const SPLIT_CHANNELS = 0x40;
+    }
+}
+
+bitflags! {
+    /// IEEE 802.15.4 MAC security levels, with bit `n` standing for security level `n`.
+    ///
+    /// See [`Radio::supported_security_levels`].
+    #[repr(transparent)]
+    #[derive(Default)]
+    #[cfg_attr(not(feature = "defmt"), derive(Debug, Copy, Clone, Eq, PartialEq, Hash))]
+    pub struct SecurityLevels: u8 {
+        /// Authentication only, with a 32-bit MIC (security level 1).
+        const MIC_32 = 1 << 1;
+        /// Authentication only, with a 64-bit MIC (security level 2).
+        const MIC_64 = 1 << 2;
+        /// Authentication only, with a 128-bit MIC (security level 3).
+        const MIC_128 = 1 << 3;
+        /// Encryption only (security level 4, deprecated by IEEE 802.15.4-2015).
+        const ENC = 1 << 4;
+        /// Encryption and authentication with a 32-bit MIC (security level 5).
+        const ENC_MIC_32 = 1 << 5;
+        /// Encryption and authentication with a 64-bit MIC (security level 6).
+        const ENC_MIC_64 = 1 << 6;
+        /// Encryption and authentication with a 128-bit MIC (security level 7).
+        const ENC_MIC_128 = 1 << 7;
     }
 }
 
@@ -172,6 +289,41 @@ pub struct Config {
     /// Extended address filter
     /// Disregarded if the radio is not capable of filtering by extended address.
     pub ext_addr: Option<u64>,
+    /// Drop received frames with a reserved or unsupported frame type (i.e. anything other than
+    /// beacon, data, ACK and MAC command frames) instead of passing them up.
+    /// Applied in software by `MacRadio`, regardless of the radio capabilities.
+    pub drop_unsupported_frames: bool,
+    /// The time (in microseconds) to wait after a channel change before the next transmission
+    /// or reception, for the radio to settle on the new channel.
+    /// `None` means the radio's own default; disregarded by radios which do not need settling time.
+    pub channel_settle_us: Option<u32>,
+    /// Passive monitor mode: receive all frames regardless of address filtering (as in promiscuous
+    /// mode, see [`Config::is_promiscuous`]), but never ACK received frames and refuse to transmit,
+    /// so that the node stays invisible to the network (e.g. for a Thread network monitor).
+    /// Frames with a bad CRC are still dropped.
+    /// `MacRadio` treats it as promiscuous mode (in which it never sends ACKs); refusing to
+    /// transmit is up to the radio.
+    pub monitor_mode: bool,
+    /// Channel to transmit on, if different from `channel` (e.g. for cross-channel test setups)
+    /// Disregarded if the radio is not capable of transmitting and receiving on different channels
+    /// (see [`MacCapabilities::SPLIT_CHANNELS`]).
+    pub tx_channel: Option<u8>,
+    /// Channel to receive on, if different from `channel`
+    /// Disregarded if the radio is not capable of transmitting and receiving on different channels
+    /// (see [`MacCapabilities::SPLIT_CHANNELS`]).
+    pub rx_channel: Option<u8>,
+    /// Beacon order (0..=15), for interop testing with beacon-enabled IEEE 802.15.4 networks
+    /// 15 (the default) means a beaconless network; standard Thread is beaconless, so
+    /// `OpenThread` never changes it.
+    pub beacon_order: u8,
+    /// Superframe order (0..=15, not larger than `beacon_order`), for interop testing with
+    /// beacon-enabled IEEE 802.15.4 networks
+    /// Ignored in beaconless networks (`beacon_order` 15); standard Thread is beaconless, so
+    /// `OpenThread` never changes it.
+    pub superframe_order: u8,
+    /// The regulatory region, to which the transmit power is clamped
+    /// (see [`Radio::regulatory_max_power`]); `None` for no clamping
+    pub region: Option<Region>,
 }
 
 impl Config {
@@ -190,8 +342,48 @@ impl Config {
             pan_id: None,
             short_addr: None,
             ext_addr: None,
+            drop_unsupported_frames: false,
+            channel_settle_us: None,
+            monitor_mode: false,
+            tx_channel: None,
+            rx_channel: None,
+            beacon_order: Self::NON_BEACON_ORDER,
+            superframe_order: Self::NON_BEACON_ORDER,
+            region: None,
+        }
+    }
+
+    /// The beacon order of beaconless networks, as well as the maximum beacon and superframe order.
+    pub const NON_BEACON_ORDER: u8 = 15;
+
+    /// Return `true` if the beacon and superframe orders are within the IEEE 802.15.4 ranges.
+    pub const fn is_superframe_valid(&self) -> bool {
+        self.beacon_order <= Self::NON_BEACON_ORDER
+            && (self.beacon_order == Self::NON_BEACON_ORDER
+                || self.superframe_order <= self.beacon_order)
+    }
+
+    /// Return the channel to transmit on: `tx_channel` if set, `channel` otherwise.
+    pub const fn effective_tx_channel(&self) -> u8 {
+        match self.tx_channel {
+            Some(channel) => channel,
+            None => self.channel,
+        }
+    }
+
+    /// Return the channel to receive on: `rx_channel` if set, `channel` otherwise.
+    pub const fn effective_rx_channel(&self) -> u8 {
+        match self.rx_channel {
+            Some(channel) => channel,
+            None => self.channel,
         }
     }
+
+    /// Return `true` if frames should be received regardless of address filtering,
+    /// i.e. in promiscuous mode or in monitor mode.
+    pub const fn is_promiscuous(&self) -> bool {
+        self.promiscuous || self.monitor_mode
+    }
 }
 
 impl Default for Config {
@@ -208,9 +400,111 @@ pub struct PsduMeta {
     pub len: usize,
     /// Channel on which the frame was received
     pub channel: u8,
+    /// Channel page of `channel` (0 for the 2.4 GHz O-QPSK PHY, which is the only PHY currently supported)
+    pub channel_page: u8,
     /// Received Signal Strength Indicator (RSSI) in dBm
     /// (if the radio supports appending it at the end of the frame, or `None` otherwise)
     pub rssi: Option<i8>,
+    /// Timestamp of the frame, in microseconds on the radio clock time base (see
+    /// [`Radio::radio_time_now`]; by default the `embassy-time` time base, i.e. comparable with
+    /// `Instant::now().as_micros()`), captured by the radio at the end of the SFD
+    /// (if the radio supports timestamping received frames, or `None` otherwise).
+    ///
+    /// The unit is always microseconds; the precision of the timestamp is reported by
+    /// [`Radio::timestamp_resolution_ns`].
+    pub timestamp: Option<u64>,
+}
+
+impl PsduMeta {
+    /// Return the link quality (0..=3) of the frame, given the noise floor (in dBm) of the channel,
+    /// or 0 if the radio did not report the RSSI of the frame.
+    ///
+    /// This is the link quality `OpenThread` keeps in its neighbor table, computed from the link
+    /// margin (the RSSI above the noise floor) with the thresholds of the `LinkQualityInfo` of
+    /// `OpenThread` (`src/core/thread/link_quality.cpp`): a link margin of at least 20 dB gives 3,
+    /// at least 10 dB gives 2, at least 2 dB gives 1, and anything lower gives 0.
+    /// The hysteresis `OpenThread` applies when updating the link quality of an existing neighbor
+    /// is not taken into account.
+    pub fn link_quality_in(&self, noise_floor: i8) -> u8 {
+        let Some(rssi) = self.rssi else {
+            return 0;
+        };
+
+        match rssi as i16 - noise_floor as i16 {
+            20.. => 3,
+            10.. => 2,
+            2.. => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// The result of an energy scan on a channel.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EnergyScanResult {
+    /// The peak signal strength measured on the channel during the scan, in dBm,
+    /// or `None` if nothing was measured
+    pub peak_rssi: Option<i8>,
+    /// The number of decodable IEEE 802.15.4 frames received on the channel during the scan
+    ///
+    /// A channel with a high energy but no decodable frames is likely occupied by
+    /// a non-802.15.4 interferer (e.g. Wi-Fi), while decodable frames indicate
+    /// other 802.15.4 (e.g. Thread) traffic.
+    pub frames_seen: u32,
+}
+
+/// An IEEE 802.15.4 MAC address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MacAddr {
+    /// A short (16-bit) address
+    Short(u16),
+    /// An extended (64-bit) address
+    Ext(u64),
+}
+
+/// The outcome of a frame transmission, as reported by radio-specific
+/// completion notifications (e.g. a TX completion callback).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TxOutcome {
+    /// The frame was transmitted
+    Sent {
+        /// Whether a CCA was performed before transmitting the frame
+        cca_performed: bool,
+    },
+    /// The frame was transmitted and the ACK it requested was received
+    ///
+    /// Reported even if the contents of the ACK were not captured.
+    Acked {
+        /// Whether a CCA was performed before transmitting the frame
+        cca_performed: bool,
+        /// The frame-pending bit of the ACK, i.e. whether the peer has more (indirect) data
+        /// for us, so that a sleepy end device which sent a data request should keep receiving.
+        ///
+        /// `true` if the contents of the ACK were not captured, so as to err on the side
+        /// of staying awake.
+        frame_pending: bool,
+    },
+    /// Transmitting the frame failed (e.g. the channel was busy or no ACK was received)
+    Failed,
+}
+
+impl TxOutcome {
+    /// Return whether a CCA was performed before transmitting the frame,
+    /// or `None` if the transmission failed.
+    ///
+    /// A CCA might be skipped even though it was requested, e.g. by radios which cannot do it;
+    /// as skipping it might be a regulatory issue, this allows checking what actually happened.
+    pub const fn cca_performed(&self) -> Option<bool> {
+        match self {
+            Self::Sent { cca_performed } | Self::Acked { cca_performed, .. } => {
+                Some(*cca_performed)
+            }
+            Self::Failed => None,
+        }
+    }
 }
 
 /// The IEEE 802.15.4 PHY Radio trait.
@@ -247,6 +541,141 @@ pub trait Radio {
     /// Set the radio configuration.
     async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error>;
 
+    /// Return `true` if the radio is currently in promiscuous mode (or in monitor mode),
+    /// as per the last configuration set with [`Radio::set_config`].
+    ///
+    /// By default, `false` is returned. Radios which support promiscuous mode should override
+    /// this method, typically with [`Config::is_promiscuous`] of their current configuration.
+    fn is_promiscuous(&self) -> bool {
+        false
+    }
+
+    /// Return the "MAC-offloading" capabilities the radio actually supports at runtime.
+    ///
+    /// [`Radio::MAC_CAPS`] describes what the radio type is capable of in general, while a concrete
+    /// radio instance (or a concrete build of its driver) might support only a subset of these.
+    /// `MacRadio` emulates in software whatever is missing from the runtime capabilities.
+    ///
+    /// The returned capabilities must be a subset of [`Radio::MAC_CAPS`].
+    /// By default, [`Radio::MAC_CAPS`] is returned.
+    fn runtime_mac_capabilities(&self) -> MacCapabilities {
+        Self::MAC_CAPS
+    }
+
+    /// Return the resolution (granularity), in nanoseconds, of the timestamps the radio reports
+    /// in [`PsduMeta::timestamp`].
+    ///
+    /// The timestamps themselves are always expressed in microseconds; a radio which captures
+    /// them with a 1 MHz clock returns 1000, while e.g. a radio capturing them with a
+    /// 32.768 kHz clock returns 30518.
+    ///
+    /// Returns 0 if the radio does not timestamp received frames, which is the default.
+    fn timestamp_resolution_ns(&self) -> u32 {
+        0
+    }
+
+    /// Return the current time of the radio clock, in microseconds.
+    ///
+    /// This is the time base of [`PsduMeta::timestamp`] (and of any other radio timing, e.g. for
+    /// CSL scheduling), so that timestamps of received frames can be related to the current time.
+    /// Radios with their own timer must convert its time to this time base.
+    ///
+    /// By default, the `embassy-time` time (`Instant::now().as_micros()`) is returned.
+    fn radio_time_now(&self) -> u64 {
+        Instant::now().as_micros()
+    }
+
+    /// Return the rated receive sensitivity of the radio, in dBm.
+    ///
+    /// This is a static figure - typically the one from the chip datasheet - rather than a measurement,
+    /// and is reported to OpenThread via `otPlatRadioGetReceiveSensitivity`. Combined with the TX power
+    /// and the noise floor, it allows applications to estimate the link budget.
+    ///
+    /// By default, -100 dBm is returned, which is the value OpenThread itself assumes for platforms
+    /// which do not report their sensitivity.
+    fn sensitivity_dbm(&self) -> i8 {
+        -100
+    }
+
+    /// Return the granularity, in dB, of the transmit power levels the radio can produce.
+    ///
+    /// A [`Config::power`] which is not a multiple of the step is quantized by the radio
+    /// to the nearest level it supports; knowing the step allows callers to enumerate the
+    /// achievable power levels instead. For radios whose power levels are not evenly spaced,
+    /// this is the smallest step.
+    ///
+    /// By default, 1 dB is returned.
+    fn power_step_db(&self) -> u8 {
+        1
+    }
+
+    /// Return the MAC security levels the radio can apply to frames by itself,
+    /// i.e. when it reports [`Capabilities::TRANSMIT_SEC`].
+    ///
+    /// Frames must not be handed over for in-radio security processing with a level not
+    /// in this set, as the radio would produce invalid frames.
+    ///
+    /// By default, no levels are returned, as MAC security is done by `OpenThread` in software.
+    fn supported_security_levels(&self) -> SecurityLevels {
+        SecurityLevels::empty()
+    }
+
+    /// Return the maximum transmit power, in dBm, allowed on the given channel in the given
+    /// regulatory region, or `None` if there is no limit for the channel (i.e. because it is not
+    /// a channel of the band). Radios clamp [`Config::power`] to it if [`Config::region`] is set.
+    ///
+    /// By default, the nominal limits of [`Region::max_power`] are returned; radios
+    /// (or rather, products) certified for lower limits (e.g. on the FCC band-edge channels)
+    /// should override this.
+    fn regulatory_max_power(&self, channel: u8, region: Region) -> Option<i8> {
+        region.max_power(channel)
+    }
+
+    /// Return the fixed latency between a [`Radio::transmit`] call (without CCA) and the start
+    /// of the frame on the air, so that callers scheduling time-sensitive frames (e.g. CSL
+    /// transmissions) can start transmitting early by that much.
+    ///
+    /// A CCA adds to it, as do the random backoffs of CSMA-CA, if the radio does these.
+    ///
+    /// By default, zero is returned.
+    fn tx_latency(&self) -> Duration {
+        Duration::from_ticks(0)
+    }
+
+    /// Return the bandwidth, in kHz, occupied by a channel of the radio's PHY,
+    /// e.g. for planning the coexistence with Wi-Fi.
+    ///
+    /// By default, 2000 kHz is returned, which is the bandwidth of the 2.4 GHz O-QPSK PHY
+    /// (with its channels spaced 5 MHz apart).
+    fn channel_bandwidth_khz(&self) -> u32 {
+        2000
+    }
+
+    /// Return the center frequency, in kHz, of the given channel,
+    /// or `None` if the radio does not support the channel.
+    ///
+    /// By default, the frequencies of the 2.4 GHz O-QPSK PHY (channel page 0) are returned:
+    /// 2405 MHz for channel 11, up to 2480 MHz for channel 26.
+    fn channel_to_frequency(&self, channel: u8) -> Option<u32> {
+        matches!(channel, 11..=26).then(|| 2_405_000 + 5_000 * (channel as u32 - 11))
+    }
+
+    /// Return the maximum number of frames per second the radio can sustain, as a
+    /// `(tx_fps, rx_fps)` tuple.
+    ///
+    /// This is meant for capacity planning, i.e. as an upper bound which applications
+    /// should stay below rather than as a rate which is guaranteed to be reached. Unless
+    /// documented otherwise by the radio, the value is theoretical (derived from the PHY
+    /// timings), not measured.
+    ///
+    /// By default, the theoretical rate of back-to-back maximum-size frames without ACKs on the
+    /// 2.4 GHz O-QPSK PHY is returned: each frame takes its airtime at 250 kbit/s (133 octets
+    /// including the SHR and PHR, 4256us) plus `macMinLIFSPeriod` (640us, which covers the CCA
+    /// and the RX-to-TX turnaround of the next frame), i.e. about 204 frames per second.
+    fn max_frame_rate(&self) -> (u32, u32) {
+        phy::max_frame_rate(false)
+    }
+
     // TODO
     //fn sleep(&mut self);
 
@@ -272,6 +701,12 @@ pub trait Radio {
     /// Returns:
     /// - The meta-data associated with the received ACK frame if the radio is capable of reporting received ACKs
     ///   and an ACK was expected and received for the transmitted frame.
+    ///
+    /// Radios are half-duplex. As both `transmit` and `receive` take `&mut self`, a transmission can
+    /// only be started once the `receive` future in progress (if any) was dropped - typically because
+    /// a `select` on it and on the next transmit request completed with the latter. Implementations
+    /// should then abort the reception and transmit right away rather than defer the transmission,
+    /// and should keep the frames already received but not yet returned, for the next `receive` call.
     async fn transmit(
         &mut self,
         psdu: &[u8],
@@ -289,6 +724,76 @@ pub trait Radio {
     async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error>;
 }
 
+/// Implement the query methods of [`Radio`] (i.e. all methods except `set_config`, `transmit`
+/// and `receive`) by delegating them to the radio which the closure-like argument returns
+/// for `self`.
+///
+/// Used by the radio wrappers, so that a query method added to the trait only needs to be
+/// delegated here.
+macro_rules! delegate_radio_queries {
+    (|$this:ident| $radio:expr) => {
+        fn is_promiscuous(&self) -> bool {
+            let $this = self;
+            Radio::is_promiscuous($radio)
+        }
+
+        fn runtime_mac_capabilities(&self) -> MacCapabilities {
+            let $this = self;
+            Radio::runtime_mac_capabilities($radio)
+        }
+
+        fn timestamp_resolution_ns(&self) -> u32 {
+            let $this = self;
+            Radio::timestamp_resolution_ns($radio)
+        }
+
+        fn radio_time_now(&self) -> u64 {
+            let $this = self;
+            Radio::radio_time_now($radio)
+        }
+
+        fn sensitivity_dbm(&self) -> i8 {
+            let $this = self;
+            Radio::sensitivity_dbm($radio)
+        }
+
+        fn power_step_db(&self) -> u8 {
+            let $this = self;
+            Radio::power_step_db($radio)
+        }
+
+        fn supported_security_levels(&self) -> SecurityLevels {
+            let $this = self;
+            Radio::supported_security_levels($radio)
+        }
+
+        fn regulatory_max_power(&self, channel: u8, region: Region) -> Option<i8> {
+            let $this = self;
+            Radio::regulatory_max_power($radio, channel, region)
+        }
+
+        fn tx_latency(&self) -> Duration {
+            let $this = self;
+            Radio::tx_latency($radio)
+        }
+
+        fn channel_bandwidth_khz(&self) -> u32 {
+            let $this = self;
+            Radio::channel_bandwidth_khz($radio)
+        }
+
+        fn channel_to_frequency(&self, channel: u8) -> Option<u32> {
+            let $this = self;
+            Radio::channel_to_frequency($radio, channel)
+        }
+
+        fn max_frame_rate(&self) -> (u32, u32) {
+            let $this = self;
+            Radio::max_frame_rate($radio)
+        }
+    };
+}
+
 impl<T> Radio for &mut T
 where
     T: Radio,
@@ -303,6 +808,8 @@ where
         T::set_config(self, config).await
     }
 
+    delegate_radio_queries!(|this| &**this);
+
     async fn transmit(
         &mut self,
         psdu: &[u8],
@@ -317,6 +824,250 @@ where
     }
 }
 
+/// A software filter for received frames, as used by [`RadioExt::receive_filtered`].
+///
+/// The filter is applied on top of whatever (hardware or `MacRadio`) filtering the radio
+/// already does, so it can only narrow down the set of delivered frames. This is mostly
+/// useful for sniffer and test scenarios, e.g. when listening to frames of a neighboring PAN
+/// on a radio running in promiscuous mode.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxFilter {
+    /// Only deliver frames addressed to this destination PAN ID, or to the
+    /// broadcast PAN ID (`0xffff`). `None` delivers frames for any PAN.
+    pub pan_id: Option<u16>,
+    /// Only deliver frames whose source address is this (PAN coordinator or parent) address,
+    /// e.g. for an end device which is only interested in the beacons and data of its parent.
+    /// Frames without a source address are dropped. `None` delivers frames from any source.
+    pub coordinator_addr: Option<MacAddr>,
+}
+
+impl RxFilter {
+    /// Create a new filter which accepts all frames.
+    pub const fn new() -> Self {
+        Self {
+            pan_id: None,
+            coordinator_addr: None,
+        }
+    }
+
+    /// Return `true` if the filter accepts all frames.
+    pub const fn is_empty(&self) -> bool {
+        self.pan_id.is_none() && self.coordinator_addr.is_none()
+    }
+
+    /// Return `true` if the frame with the given PSDU passes the filter.
+    ///
+    /// Frames whose MAC header cannot be parsed only pass an empty filter.
+    pub fn matches(&self, psdu: &[u8]) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let mut header = MacHeader::new();
+        if header.load(psdu).is_none() {
+            return false;
+        }
+
+        if let Some(pan_id) = self.pan_id {
+            if header.pan_id != MacHeader::BROADCAST_PAN_ID && header.pan_id != pan_id {
+                return false;
+            }
+        }
+
+        if let Some(coordinator_addr) = self.coordinator_addr {
+            if header.src_addr(psdu) != Some(coordinator_addr) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Extension methods available on every [`Radio`].
+pub trait RadioExt: Radio {
+    /// Receive a radio frame which passes the provided filter.
+    ///
+    /// Frames not passing the filter are silently dropped and the method keeps receiving.
+    ///
+    /// Arguments:
+    /// - `psdu_buf`: The buffer to store the received PSDU.
+    /// - `filter`: The filter the received frame should pass.
+    ///
+    /// Returns:
+    /// - The meta-data associated with the received frame.
+    async fn receive_filtered(
+        &mut self,
+        psdu_buf: &mut [u8],
+        filter: &RxFilter,
+    ) -> Result<PsduMeta, Self::Error> {
+        loop {
+            let psdu_meta = self.receive(psdu_buf).await?;
+
+            let psdu = &psdu_buf[..psdu_meta.len];
+
+            if filter.matches(psdu) {
+                break Ok(psdu_meta);
+            }
+
+            trace!("Filtering out frame: {}, filter: {:?}", Bytes(psdu), filter);
+        }
+    }
+
+    /// Receive a radio frame into a buffer taken from the provided pool.
+    ///
+    /// The returned frame holds on to its pool buffer until it is dropped, so several frames
+    /// can be kept around (e.g. for processing them asynchronously) without the caller managing
+    /// buffers. If all pool buffers are in use, the method waits for one to be released before
+    /// receiving.
+    ///
+    /// Arguments:
+    /// - `pool`: The pool to take the receive buffer from.
+    ///
+    /// Returns:
+    /// - The received frame.
+    async fn receive_pooled<'p, const N: usize, const LEN: usize>(
+        &mut self,
+        pool: &'p RxPool<N, LEN>,
+    ) -> Result<PooledFrame<'p, N, LEN>, Self::Error> {
+        let mut buf = pool.alloc().await;
+
+        let meta = self.receive(buf.as_mut()).await?;
+
+        Ok(PooledFrame { buf, meta })
+    }
+
+    /// Transmit a radio frame and then immediately receive for up to the provided window,
+    /// e.g. for the indirect data a parent sends to a sleepy end device after a data poll.
+    ///
+    /// The receive window opens right after the transmission completes (including the ACK,
+    /// if one was requested), so that a response which follows shortly is not missed. It is
+    /// opened regardless of the frame-pending bit of the ACK; callers which only want to receive
+    /// if the peer has pending data should check [`TxOutcome::Acked`] themselves.
+    ///
+    /// Arguments:
+    /// - `psdu`: The PSDU to transmit.
+    /// - `cca`: Whether to perform a CCA before transmitting.
+    /// - `rx_window`: For how long to receive after the transmission.
+    /// - `rx_buf`: The buffer to store the received PSDU.
+    ///
+    /// Returns:
+    /// - The outcome of the transmission ([`TxOutcome::Sent`] or [`TxOutcome::Acked`]), and the
+    ///   meta-data of the received frame, or `None` if no frame was received within the window.
+    async fn transmit_then_receive(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        rx_window: Duration,
+        rx_buf: &mut [u8],
+    ) -> Result<(TxOutcome, Option<PsduMeta>), Self::Error> {
+        let outcome = transmit_for_outcome(self, psdu, cca).await?;
+
+        let rx_meta = match with_timeout(rx_window, self.receive(rx_buf)).await {
+            Ok(result) => Some(result?),
+            Err(_) => None,
+        };
+
+        Ok((outcome, rx_meta))
+    }
+
+    /// Transmit a radio frame and then wait for a higher-layer response frame which is matched
+    /// by the provided closure (e.g. by comparing a request token carried in its payload).
+    ///
+    /// This is unrelated to MAC ACKs: a MAC ACK only confirms that the peer's radio received
+    /// the frame and is handled by the radio itself (as part of the transmission), whereas
+    /// the response awaited here is an ordinary data frame sent by the peer's application,
+    /// which might also arrive after other, unrelated frames. The MAC ACK outcome of the
+    /// transmission is still returned, so a missing response can be told apart from a frame
+    /// which never reached the peer.
+    ///
+    /// As with [`RadioExt::transmit_then_receive`], the response window opens right after the
+    /// transmission completes. It closes once `timeout` has elapsed since the start of the call,
+    /// i.e. the transmission itself (including its ACK) counts towards the timeout as well.
+    /// Frames not matching are silently dropped, and receiving continues within the same window.
+    ///
+    /// Arguments:
+    /// - `psdu`: The PSDU to transmit.
+    /// - `cca`: Whether to perform a CCA before transmitting.
+    /// - `timeout`: For how long to wait for the response.
+    /// - `rx_buf`: The buffer to store the response PSDU.
+    /// - `matches`: Returns `true` if the provided received PSDU is the expected response.
+    ///
+    /// Returns:
+    /// - The outcome of the transmission, and the meta-data of the response frame (stored in
+    ///   `rx_buf`), or `None` if no matching frame was received in time.
+    async fn transmit_confirmed<F>(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        timeout: Duration,
+        rx_buf: &mut [u8],
+        mut matches: F,
+    ) -> Result<(TxOutcome, Option<PsduMeta>), Self::Error>
+    where
+        F: FnMut(&[u8]) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        let outcome = transmit_for_outcome(self, psdu, cca).await?;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            let Ok(result) = with_timeout(remaining, self.receive(rx_buf)).await else {
+                break Ok((outcome, None));
+            };
+
+            let meta = result?;
+
+            if matches(&rx_buf[..meta.len]) {
+                break Ok((outcome, Some(meta)));
+            }
+
+            trace!(
+                "Dropping non-matching response: {}",
+                Bytes(&rx_buf[..meta.len])
+            );
+        }
+    }
+}
+
+impl<T> RadioExt for T where T: Radio {}
+
+/// Transmit a radio frame and return the outcome of the transmission,
+/// for [`RadioExt::transmit_then_receive`] and [`RadioExt::transmit_confirmed`].
+async fn transmit_for_outcome<R>(
+    radio: &mut R,
+    psdu: &[u8],
+    cca: bool,
+) -> Result<TxOutcome, R::Error>
+where
+    R: Radio + ?Sized,
+{
+    let mut ack_psdu_buf = [0_u8; OT_RADIO_FRAME_MAX_SIZE as _];
+
+    let ack_meta = radio.transmit(psdu, cca, Some(&mut ack_psdu_buf)).await?;
+
+    // The `Radio` trait has no way to report a skipped CCA, so assume the request was honored
+    let outcome = if let Some(ack_meta) = ack_meta {
+        TxOutcome::Acked {
+            cca_performed: cca,
+            frame_pending: MacHeader::frame_pending(&ack_psdu_buf[..ack_meta.len]).unwrap_or(true),
+        }
+    } else if MacHeader::ack_requested(psdu).unwrap_or(false) {
+        // The radio got the ACK but did not capture it
+        TxOutcome::Acked {
+            cca_performed: cca,
+            frame_pending: true,
+        }
+    } else {
+        TxOutcome::Sent { cca_performed: cca }
+    };
+
+    Ok(outcome)
+}
+
 /// An error type for the enhanced radio.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum MacRadioError<T> {
@@ -383,6 +1134,8 @@ pub struct MacRadio<R, T> {
     timer: T,
     /// Whether the radio is in promiscuous mode.
     promiscuous: bool,
+    /// Whether to drop received frames with a reserved or unsupported frame type.
+    drop_unsupported_frames: bool,
     /// A buffer for the MAC header of the received or transmitted frame.
     /// (For filtering and ACKs)
     mac_header: MacHeader,
@@ -426,6 +1179,7 @@ where
             mac_header: MacHeader::new(),
             ack_psdu_buf: [0; OT_RADIO_FRAME_MAX_SIZE as _],
             promiscuous: false,
+            drop_unsupported_frames: false,
             pan_id: MacHeader::BROADCAST_PAN_ID,
             short_addr: MacHeader::BROADCAST_SHORT_ADDR,
             ext_addr: MacHeader::BROADCAST_EXT_ADDR,
@@ -450,7 +1204,8 @@ where
             .await
             .map_err(Self::Error::Io)?;
 
-        self.promiscuous = config.promiscuous;
+        self.promiscuous = config.is_promiscuous();
+        self.drop_unsupported_frames = config.drop_unsupported_frames;
         self.pan_id = config.pan_id.unwrap_or(MacHeader::BROADCAST_PAN_ID);
         self.short_addr = config.short_addr.unwrap_or(MacHeader::BROADCAST_SHORT_ADDR);
         self.ext_addr = config.ext_addr.unwrap_or(MacHeader::BROADCAST_EXT_ADDR);
@@ -458,6 +1213,8 @@ where
         Ok(())
     }
 
+    delegate_radio_queries!(|this| &this.radio);
+
     async fn transmit(
         &mut self,
         psdu: &[u8],
@@ -466,7 +1223,11 @@ where
     ) -> Result<Option<PsduMeta>, Self::Error> {
         trace!("MacRadio, about to transmit");
 
-        if R::MAC_CAPS.contains(MacCapabilities::TX_ACK) {
+        if self
+            .radio
+            .runtime_mac_capabilities()
+            .contains(MacCapabilities::TX_ACK)
+        {
             let result = self
                 .radio
                 .transmit(psdu, cca, ack_psdu_buf)
@@ -548,7 +1309,17 @@ where
 
             trace!("MacRadio, received: {}, meta: {:?}", Bytes(psdu), psdu_meta);
 
-            if R::MAC_CAPS != MacCapabilities::all() {
+            if self.drop_unsupported_frames && !MacHeader::has_supported_frame_type(psdu) {
+                trace!(
+                    "MacRadio, received frame with unsupported frame type, dropping: {}",
+                    Bytes(psdu)
+                );
+                continue;
+            }
+
+            let mac_caps = self.radio.runtime_mac_capabilities();
+
+            if mac_caps != MacCapabilities::all() {
                 if self.mac_header.load(psdu).is_none() {
                     trace!(
                         "MacRadio, received frame with invalid MAC header, dropping: {}",
@@ -557,8 +1328,8 @@ where
                     continue;
                 }
 
-                if !R::MAC_CAPS.contains(MacCapabilities::PROMISCUOUS) && !self.promiscuous {
-                    if !R::MAC_CAPS.contains(MacCapabilities::FILTER_PAN_ID)
+                if !mac_caps.contains(MacCapabilities::PROMISCUOUS) && !self.promiscuous {
+                    if !mac_caps.contains(MacCapabilities::FILTER_PAN_ID)
                         && self.mac_header.pan_id != MacHeader::BROADCAST_PAN_ID
                         && self.mac_header.pan_id != self.pan_id
                     {
@@ -569,7 +1340,7 @@ where
                         continue;
                     }
 
-                    if !R::MAC_CAPS.contains(MacCapabilities::FILTER_SHORT_ADDR)
+                    if !mac_caps.contains(MacCapabilities::FILTER_SHORT_ADDR)
                         && self.mac_header.dst_short_addr != MacHeader::BROADCAST_SHORT_ADDR
                         && self.mac_header.dst_short_addr != self.short_addr
                     {
@@ -580,7 +1351,7 @@ where
                         continue;
                     }
 
-                    if !R::MAC_CAPS.contains(MacCapabilities::FILTER_EXT_ADDR)
+                    if !mac_caps.contains(MacCapabilities::FILTER_EXT_ADDR)
                         && self.mac_header.dst_ext_addr != MacHeader::BROADCAST_EXT_ADDR
                         && self.mac_header.dst_ext_addr != self.ext_addr
                     {
@@ -591,8 +1362,7 @@ where
                         continue;
                     }
 
-                    if !R::MAC_CAPS.contains(MacCapabilities::RX_ACK) && self.mac_header.needs_ack()
-                    {
+                    if !mac_caps.contains(MacCapabilities::RX_ACK) && self.mac_header.needs_ack() {
                         let ack_len = self.mac_header.prep_ack(&mut self.ack_psdu_buf);
                         let ack_psdu = &mut self.ack_psdu_buf[..ack_len];
 
@@ -612,56 +1382,1065 @@ where
 
             trace!("MacRadio, received frame: {}", Bytes(psdu));
 
-            break Ok(psdu_meta);
-        }
+            break Ok(psdu_meta);
+        }
+    }
+}
+
+/// A radio wrapper that sends - in software - Enh-ACKs for received ACK-requesting
+/// frame-version-2 (IEEE 802.15.4-2015) frames, for radios which cannot generate Enh-ACKs
+/// themselves.
+///
+/// The wrapped radio is expected to do everything else: address filtering, and Imm-ACKs for
+/// frame-version-0 and -1 frames (i.e. wrapping a radio without these capabilities in `MacRadio`
+/// first). It must not ACK frame-version-2 frames by itself (e.g. [`crate::esp::EspRadio`] should
+/// be created with `with_enhanced_ack(false)`), and no Enh-ACKs are sent while it is
+/// in promiscuous mode.
+///
+/// NOTE: The Enh-ACK must go on the air within `aTurnaroundTime` (192us) after the end of the
+/// acknowledged frame, so the wrapper only works with radios (and executors) fast enough
+/// to hand over the received frame and to start the ACK transmission within that window;
+/// the ACK is transmitted right away, without CCA. Also note that the generated Enh-ACKs are
+/// never secured, so peers requiring secured Enh-ACKs for secured frames will reject them.
+pub struct SoftEnhancedAckRadio<'a, R> {
+    /// The wrapped radio
+    radio: R,
+    /// The (already encoded) header IEs to include in the Enh-ACKs
+    header_ies: &'a [u8],
+    /// The buffer for the Enh-ACK PSDU
+    ack_psdu_buf: [u8; PSDU_LEN],
+}
+
+impl<'a, R> SoftEnhancedAckRadio<'a, R>
+where
+    R: Radio,
+{
+    /// Create a new `SoftEnhancedAckRadio`, sending Enh-ACKs without IEs.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to wrap.
+    pub const fn new(radio: R) -> Self {
+        Self {
+            radio,
+            header_ies: &[],
+            ack_psdu_buf: [0; PSDU_LEN],
+        }
+    }
+
+    /// Set the header IEs to include in the Enh-ACKs (e.g. a CSL IE).
+    ///
+    /// The IEs are included as-is, so they must already be encoded (IE descriptors included).
+    /// Enh-ACKs which would be too long with the IEs included are not sent.
+    pub fn set_header_ies(&mut self, header_ies: &'a [u8]) {
+        self.header_ies = header_ies;
+    }
+
+    /// Return the wrapped radio.
+    pub fn release(self) -> R {
+        self.radio
+    }
+}
+
+impl<R> Radio for SoftEnhancedAckRadio<'_, R>
+where
+    R: Radio,
+{
+    type Error = R::Error;
+
+    const CAPS: Capabilities = R::CAPS;
+
+    const MAC_CAPS: MacCapabilities = R::MAC_CAPS;
+
+    async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error> {
+        self.radio.set_config(config).await
+    }
+
+    delegate_radio_queries!(|this| &this.radio);
+
+    async fn transmit(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, Self::Error> {
+        self.radio.transmit(psdu, cca, ack_psdu_buf).await
+    }
+
+    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+        let psdu_meta = self.radio.receive(psdu_buf).await?;
+
+        if !self.radio.is_promiscuous() {
+            let psdu = &psdu_buf[..psdu_meta.len];
+
+            if let Some(ack_len) =
+                MacHeader::prep_enh_ack(psdu, self.header_ies, &mut self.ack_psdu_buf)
+            {
+                let ack_psdu = &self.ack_psdu_buf[..ack_len];
+
+                trace!(
+                    "SoftEnhancedAckRadio, about to transmit Enh-ACK: {}",
+                    Bytes(ack_psdu)
+                );
+
+                if let Err(e) = self.radio.transmit(ack_psdu, false, None).await {
+                    // Still deliver the frame; the peer will just retransmit it
+                    warn!(
+                        "SoftEnhancedAckRadio, Enh-ACK transmission failed: {:?}",
+                        e.kind()
+                    );
+                }
+            }
+        }
+
+        Ok(psdu_meta)
+    }
+}
+
+/// A neighbor heard by a [`NeighborObserverRadio`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeighborInfo {
+    /// The source address of the frames received from the neighbor
+    pub addr: MacAddr,
+    /// When the last frame from the neighbor was received
+    pub last_seen: Instant,
+    /// The RSSI of the last frame from the neighbor, if the radio reported it
+    pub rssi: Option<i8>,
+    /// The number of frames received from the neighbor (wraps around on overflow)
+    pub frames: u32,
+}
+
+/// A radio wrapper which keeps a table of the neighbors heard recently, derived purely from
+/// the source addresses of the received frames.
+///
+/// This gives a lightweight view of the nodes around, without querying the `OpenThread` stack.
+/// Frames without a source address (e.g. ACKs) or with a frame version other than 2003/2006
+/// are not taken into account. A neighbor using both its short and its extended address shows up
+/// as two entries.
+///
+/// The table has room for `N` neighbors. Neighbors not heard for longer than the maximum age
+/// (see [`NeighborObserverRadio::with_max_age`]) are considered stale: they are not reported
+/// and their entries are reused first; if no entry is stale, the least recently heard neighbor
+/// is evicted.
+pub struct NeighborObserverRadio<R, const N: usize> {
+    radio: R,
+    neighbors: [Option<NeighborInfo>; N],
+    max_age: Duration,
+}
+
+impl<R, const N: usize> NeighborObserverRadio<R, N>
+where
+    R: Radio,
+{
+    /// The default maximum age of the neighbors in the table.
+    pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60);
+
+    /// Create a new `NeighborObserverRadio`.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to wrap.
+    pub const fn new(radio: R) -> Self {
+        Self {
+            radio,
+            neighbors: [None; N],
+            max_age: Self::DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Set after how long without hearing from a neighbor its entry becomes stale.
+    #[must_use]
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Return the neighbors heard within the maximum age, in no particular order.
+    pub fn neighbors(&self) -> impl Iterator<Item = NeighborInfo> + '_ {
+        let now = Instant::now();
+
+        self.neighbors
+            .iter()
+            .flatten()
+            .filter(move |neighbor| !self.is_stale(neighbor, now))
+            .copied()
+    }
+
+    /// Forget all neighbors.
+    pub fn clear(&mut self) {
+        self.neighbors = [None; N];
+    }
+
+    /// Return the wrapped radio.
+    pub fn release(self) -> R {
+        self.radio
+    }
+
+    fn is_stale(&self, neighbor: &NeighborInfo, now: Instant) -> bool {
+        now.saturating_duration_since(neighbor.last_seen) > self.max_age
+    }
+
+    fn observe(&mut self, psdu: &[u8], rssi: Option<i8>) {
+        let mut header = MacHeader::new();
+        if header.load(psdu).is_none() {
+            return;
+        }
+
+        let Some(addr) = header.src_addr(psdu) else {
+            return;
+        };
+
+        let now = Instant::now();
+
+        if let Some(neighbor) = self
+            .neighbors
+            .iter_mut()
+            .flatten()
+            .find(|neighbor| neighbor.addr == addr)
+        {
+            neighbor.last_seen = now;
+            neighbor.rssi = rssi;
+            neighbor.frames = neighbor.frames.wrapping_add(1);
+
+            return;
+        }
+
+        // Prefer a free entry, then a stale one, then the least recently heard one
+        let slot = self
+            .neighbors
+            .iter()
+            .position(Option::is_none)
+            .or_else(|| {
+                self.neighbors
+                    .iter()
+                    .position(|neighbor| neighbor.is_some_and(|n| self.is_stale(&n, now)))
+            })
+            .or_else(|| {
+                self.neighbors
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, neighbor)| neighbor.map(|n| n.last_seen))
+                    .map(|(index, _)| index)
+            });
+
+        if let Some(slot) = slot {
+            self.neighbors[slot] = Some(NeighborInfo {
+                addr,
+                last_seen: now,
+                rssi,
+                frames: 1,
+            });
+        }
+    }
+}
+
+impl<R, const N: usize> Radio for NeighborObserverRadio<R, N>
+where
+    R: Radio,
+{
+    type Error = R::Error;
+
+    const CAPS: Capabilities = R::CAPS;
+
+    const MAC_CAPS: MacCapabilities = R::MAC_CAPS;
+
+    async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error> {
+        self.radio.set_config(config).await
+    }
+
+    delegate_radio_queries!(|this| &this.radio);
+
+    async fn transmit(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, Self::Error> {
+        self.radio.transmit(psdu, cca, ack_psdu_buf).await
+    }
+
+    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+        let psdu_meta = self.radio.receive(psdu_buf).await?;
+
+        self.observe(&psdu_buf[..psdu_meta.len], psdu_meta.rssi);
+
+        Ok(psdu_meta)
+    }
+}
+
+/// A radio wrapper which drops received frames repeating the sequence number of the previous
+/// frame from the same source, i.e. the duplicates produced by a retransmission after a lost ACK.
+///
+/// Only the immediately preceding sequence number of each source is remembered, which cheaply
+/// catches the common single-retransmit duplicate. Frames without a source address (e.g. ACKs) or
+/// with a frame version other than 2003/2006 are always let through.
+///
+/// The duplicates must still be ACKed (their ACK was lost after all), so if the ACKs are sent
+/// in software, this wrapper should wrap the [`MacRadio`] rather than the other way around.
+///
+/// The sequence numbers of up to `N` sources are tracked; when the table is full, the entries
+/// are reused in a round-robin fashion.
+pub struct DuplicateFilterRadio<R, const N: usize> {
+    radio: R,
+    last_seqs: [Option<(MacAddr, u8)>; N],
+    next_slot: usize,
+    duplicates: u32,
+}
+
+impl<R, const N: usize> DuplicateFilterRadio<R, N>
+where
+    R: Radio,
+{
+    /// Create a new `DuplicateFilterRadio`.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to wrap.
+    pub const fn new(radio: R) -> Self {
+        Self {
+            radio,
+            last_seqs: [None; N],
+            next_slot: 0,
+            duplicates: 0,
+        }
+    }
+
+    /// Return the number of duplicate frames dropped so far.
+    ///
+    /// The counter wraps around on overflow.
+    pub fn duplicates(&self) -> u32 {
+        self.duplicates
+    }
+
+    /// Return the wrapped radio.
+    pub fn release(self) -> R {
+        self.radio
+    }
+
+    /// Record the sequence number of the frame, returning `true` if the frame is a duplicate.
+    fn is_duplicate(&mut self, psdu: &[u8]) -> bool {
+        let mut header = MacHeader::new();
+        if header.load(psdu).is_none() {
+            return false;
+        }
+
+        let Some(addr) = header.src_addr(psdu) else {
+            return false;
+        };
+
+        if let Some((_, last_seq)) = self
+            .last_seqs
+            .iter_mut()
+            .flatten()
+            .find(|(last_addr, _)| *last_addr == addr)
+        {
+            let duplicate = *last_seq == header.seq;
+            *last_seq = header.seq;
+
+            return duplicate;
+        }
+
+        if N > 0 {
+            self.last_seqs[self.next_slot] = Some((addr, header.seq));
+            self.next_slot = (self.next_slot + 1) % N;
+        }
+
+        false
+    }
+}
+
+impl<R, const N: usize> Radio for DuplicateFilterRadio<R, N>
+where
+    R: Radio,
+{
+    type Error = R::Error;
+
+    const CAPS: Capabilities = R::CAPS;
+
+    const MAC_CAPS: MacCapabilities = R::MAC_CAPS;
+
+    async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error> {
+        self.radio.set_config(config).await
+    }
+
+    delegate_radio_queries!(|this| &this.radio);
+
+    async fn transmit(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, Self::Error> {
+        self.radio.transmit(psdu, cca, ack_psdu_buf).await
+    }
+
+    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+        loop {
+            let psdu_meta = self.radio.receive(psdu_buf).await?;
+
+            let psdu = &psdu_buf[..psdu_meta.len];
+
+            if !self.is_duplicate(psdu) {
+                break Ok(psdu_meta);
+            }
+
+            self.duplicates = self.duplicates.wrapping_add(1);
+
+            trace!(
+                "DuplicateFilterRadio, dropping duplicate frame: {}",
+                Bytes(psdu)
+            );
+        }
+    }
+}
+
+/// The parameters of a token bucket rate limit. See [`RateLimitRadio`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TokenBucket {
+    /// The maximum number of frames which can be transmitted in a burst
+    pub burst: u32,
+    /// The number of frames per second by which the allowance is replenished
+    pub frames_per_sec: u32,
+}
+
+/// The state of a token bucket rate limit.
+struct TokenBucketState {
+    limit: Option<TokenBucket>,
+    tokens: u32,
+    refilled_at: Instant,
+}
+
+impl TokenBucketState {
+    const fn new(limit: Option<TokenBucket>) -> Self {
+        Self {
+            limit,
+            tokens: match limit {
+                Some(limit) => limit.burst,
+                None => 0,
+            },
+            refilled_at: Instant::from_ticks(0),
+        }
+    }
+
+    /// Take a token from the bucket, returning `false` if the bucket is empty.
+    fn admit(&mut self, now: Instant) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+
+        if limit.frames_per_sec > 0 {
+            let elapsed_us = now.saturating_duration_since(self.refilled_at).as_micros();
+            let added = elapsed_us * limit.frames_per_sec as u64 / 1_000_000;
+
+            if added > 0 {
+                let tokens = (self.tokens as u64 + added).min(limit.burst as u64) as u32;
+
+                // Carry over the time not yet worth a whole token, unless the bucket is full
+                self.refilled_at = if tokens == limit.burst {
+                    now
+                } else {
+                    self.refilled_at
+                        + Duration::from_micros(added * 1_000_000 / limit.frames_per_sec as u64)
+                };
+                self.tokens = tokens;
+            }
+        }
+
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// An error type for the [`RateLimitRadio`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RateLimitRadioError<T> {
+    /// Transmitting was refused because the rate limit was exceeded
+    RateLimited,
+    /// Error coming from the wrapped radio
+    Io(T),
+}
+
+impl<T> RadioError for RateLimitRadioError<T>
+where
+    T: RadioError,
+{
+    fn kind(&self) -> RadioErrorKind {
+        match self {
+            Self::RateLimited => RadioErrorKind::RateLimited,
+            Self::Io(e) => e.kind(),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for RateLimitRadioError<T>
+where
+    T: RadioError,
+{
+    fn format(&self, fmt: defmt::Formatter<'_>) {
+        defmt::write!(fmt, "{}", self.kind())
+    }
+}
+
+/// A radio wrapper which caps the rate of the transmitted frames with token buckets, protecting
+/// the airtime and the node itself from broadcast storms caused by misbehaving peers or bugs.
+///
+/// Broadcast frames (i.e. frames without a unicast destination address) and unicast frames
+/// are limited separately; frames whose MAC header cannot be parsed (e.g. frames with
+/// frame version 2015) count as unicast. Transmissions over the limit fail with
+/// [`RateLimitRadioError::RateLimited`] without reaching the wrapped radio.
+///
+/// By default, neither kind of frames is limited.
+pub struct RateLimitRadio<R> {
+    radio: R,
+    broadcast: TokenBucketState,
+    unicast: TokenBucketState,
+    rate_limited: u32,
+}
+
+impl<R> RateLimitRadio<R>
+where
+    R: Radio,
+{
+    /// Create a new `RateLimitRadio`.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to wrap.
+    pub const fn new(radio: R) -> Self {
+        Self {
+            radio,
+            broadcast: TokenBucketState::new(None),
+            unicast: TokenBucketState::new(None),
+            rate_limited: 0,
+        }
+    }
+
+    /// Set the rate limit of the broadcast frames (`None` for no limit).
+    #[must_use]
+    pub fn with_broadcast_limit(mut self, limit: Option<TokenBucket>) -> Self {
+        self.broadcast = TokenBucketState::new(limit);
+        self
+    }
+
+    /// Set the rate limit of the unicast frames (`None` for no limit).
+    #[must_use]
+    pub fn with_unicast_limit(mut self, limit: Option<TokenBucket>) -> Self {
+        self.unicast = TokenBucketState::new(limit);
+        self
+    }
+
+    /// Return the number of transmissions refused because of the rate limits so far.
+    ///
+    /// The counter wraps around on overflow.
+    pub fn rate_limited(&self) -> u32 {
+        self.rate_limited
+    }
+
+    /// Return the wrapped radio.
+    pub fn release(self) -> R {
+        self.radio
+    }
+}
+
+impl<R> Radio for RateLimitRadio<R>
+where
+    R: Radio,
+{
+    type Error = RateLimitRadioError<R::Error>;
+
+    const CAPS: Capabilities = R::CAPS;
+
+    const MAC_CAPS: MacCapabilities = R::MAC_CAPS;
+
+    async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error> {
+        self.radio
+            .set_config(config)
+            .await
+            .map_err(RateLimitRadioError::Io)
+    }
+
+    delegate_radio_queries!(|this| &this.radio);
+
+    async fn transmit(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, Self::Error> {
+        let mut header = MacHeader::new();
+
+        let broadcast = header.load(psdu).is_some()
+            && header.dst_short_addr == MacHeader::BROADCAST_SHORT_ADDR
+            && header.dst_ext_addr == MacHeader::BROADCAST_EXT_ADDR;
+
+        let bucket = if broadcast {
+            &mut self.broadcast
+        } else {
+            &mut self.unicast
+        };
+
+        if !bucket.admit(Instant::now()) {
+            self.rate_limited = self.rate_limited.wrapping_add(1);
+
+            debug!(
+                "RateLimitRadio, {} rate limit exceeded, dropping frame: {}",
+                if broadcast { "broadcast" } else { "unicast" },
+                Bytes(psdu)
+            );
+
+            return Err(RateLimitRadioError::RateLimited);
+        }
+
+        self.radio
+            .transmit(psdu, cca, ack_psdu_buf)
+            .await
+            .map_err(RateLimitRadioError::Io)
+    }
+
+    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+        self.radio
+            .receive(psdu_buf)
+            .await
+            .map_err(RateLimitRadioError::Io)
+    }
+}
+
+/// A radio which can be split into a TX-only and an RX-only half, so that one task can transmit
+/// while another one receives, without the two sharing a `&mut` reference to the radio.
+///
+/// As the radio is half-duplex, the halves arbitrate the access to it:
+/// - A transmission (or a configuration change) always takes precedence: if the [`RxHalf`] is
+///   receiving, its reception is cancelled (i.e. the [`Radio::receive`] future of the
+///   wrapped radio is dropped), and the radio is handed over to the [`TxHalf`].
+/// - Once the transmission is done (or its future is dropped), the [`RxHalf`] resumes receiving.
+///
+/// Hence, the wrapped radio must support cancelling a reception at any time, as is anyway
+/// required by `OpenThread`. A frame which is on the air while the radio transmits is lost.
+///
+/// The futures of the [`TxHalf`] are cancel-safe with regards to the [`RxHalf`]: dropping
+/// one at any point - including while it still waits for the [`RxHalf`] to give the radio
+/// up - lets the [`RxHalf`] resume receiving.
+pub struct SplitRadio<R> {
+    radio: AsyncMutex<CriticalSectionRawMutex, R>,
+    tx_request: Signal<CriticalSectionRawMutex, ()>,
+    tx_done: Signal<CriticalSectionRawMutex, ()>,
+}
+
+impl<R> SplitRadio<R>
+where
+    R: Radio,
+{
+    /// Create a new `SplitRadio`.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to wrap.
+    pub const fn new(radio: R) -> Self {
+        Self {
+            radio: AsyncMutex::new(radio),
+            tx_request: Signal::new(),
+            tx_done: Signal::new(),
+        }
+    }
+
+    /// Split the radio into its TX and RX halves.
+    pub fn split(&mut self) -> (TxHalf<'_, R>, RxHalf<'_, R>) {
+        let this = &*self;
+
+        (TxHalf { shared: this }, RxHalf { shared: this })
+    }
+
+    /// Return the wrapped radio.
+    pub fn release(self) -> R {
+        self.radio.into_inner()
+    }
+
+    /// Take the radio over from the receiving half, if it is busy receiving.
+    async fn lock_for_tx(&self) -> TxTurn<'_, R> {
+        self.tx_done.reset();
+        self.tx_request.signal(());
+
+        // Created before waiting for the radio, so that the RX half resumes receiving
+        // even if this future is dropped before the radio is locked
+        let request = TxRequest {
+            tx_request: &self.tx_request,
+            tx_done: &self.tx_done,
+        };
+
+        let radio = self.radio.lock().await;
+
+        // Nobody is receiving anymore, so no need to signal a cancellation
+        self.tx_request.reset();
+
+        TxTurn {
+            radio,
+            _request: request,
+        }
+    }
+}
+
+/// The TX half of a [`SplitRadio`].
+pub struct TxHalf<'a, R> {
+    shared: &'a SplitRadio<R>,
+}
+
+impl<R> TxHalf<'_, R>
+where
+    R: Radio,
+{
+    /// Set the radio configuration, preempting the [`RxHalf`] if it is receiving.
+    /// See [`Radio::set_config`].
+    pub async fn set_config(&mut self, config: &Config) -> Result<(), R::Error> {
+        self.shared
+            .lock_for_tx()
+            .await
+            .radio
+            .set_config(config)
+            .await
+    }
+
+    /// Transmit a radio frame, preempting the [`RxHalf`] if it is receiving.
+    /// See [`Radio::transmit`].
+    pub async fn transmit(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, R::Error> {
+        self.shared
+            .lock_for_tx()
+            .await
+            .radio
+            .transmit(psdu, cca, ack_psdu_buf)
+            .await
+    }
+}
+
+/// The RX half of a [`SplitRadio`].
+pub struct RxHalf<'a, R> {
+    shared: &'a SplitRadio<R>,
+}
+
+impl<R> RxHalf<'_, R>
+where
+    R: Radio,
+{
+    /// Receive a radio frame, giving way to the transmissions of the [`TxHalf`].
+    /// See [`Radio::receive`].
+    pub async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, R::Error> {
+        loop {
+            let mut radio = self.shared.radio.lock().await;
+
+            match select(radio.receive(psdu_buf), self.shared.tx_request.wait()).await {
+                Either::First(result) => break result,
+                Either::Second(_) => {
+                    trace!("SplitRadio, RX preempted by TX");
+
+                    drop(radio);
+
+                    // Do not grab the radio again before the transmission is over
+                    self.shared.tx_done.wait().await;
+                }
+            }
+        }
+    }
+}
+
+/// The radio, as locked by the [`TxHalf`] of a [`SplitRadio`].
+///
+/// Unlocks the radio and lets the [`RxHalf`] resume receiving when dropped, i.e. when
+/// the transmission is done or its future is dropped.
+struct TxTurn<'a, R> {
+    radio: MutexGuard<'a, CriticalSectionRawMutex, R>,
+    _request: TxRequest<'a>,
+}
+
+/// A pending request of the [`TxHalf`] of a [`SplitRadio`] for the radio.
+///
+/// Withdraws the request and lets the [`RxHalf`] resume receiving when dropped.
+struct TxRequest<'a> {
+    tx_request: &'a Signal<CriticalSectionRawMutex, ()>,
+    tx_done: &'a Signal<CriticalSectionRawMutex, ()>,
+}
+
+impl Drop for TxRequest<'_> {
+    fn drop(&mut self) {
+        self.tx_request.reset();
+        self.tx_done.signal(());
+    }
+}
+
+/// A high-res timer trait that is necessary for the `MacRadio` to send ACKs
+/// at the right time.
+///
+/// The timer should have a microsecond, or a few microseconds' resolution.
+pub trait MacRadioTimer {
+    /// Returns the current time - in microseconds - from some predefined period in time
+    /// The returned time should be monotonic.
+    fn now(&mut self) -> u64;
+
+    /// Waits until the current time becomes equal or greater than the given time.
+    ///
+    /// If the current time is already equal or greater than the given time,
+    /// the function should return immediately, without waiting.
+    ///
+    /// Arguments:
+    /// - `time`: The time - in microseconds - to wait until.
+    async fn wait(&mut self, at: u64);
+}
+
+impl<T> MacRadioTimer for &mut T
+where
+    T: MacRadioTimer,
+{
+    fn now(&mut self) -> u64 {
+        T::now(self)
+    }
+
+    async fn wait(&mut self, at: u64) {
+        T::wait(self, at).await
+    }
+}
+
+/// An implementation of `MacRadioTimer` that uses the `embassy_time` crate.
+///
+/// Note that this implementation might NOT be appropriate when the concrete
+/// `embassy-time` implementation is not having a high-enough resolution.
+pub struct EmbassyTimeTimer;
+
+impl MacRadioTimer for EmbassyTimeTimer {
+    fn now(&mut self) -> u64 {
+        Instant::now().as_micros()
+    }
+
+    async fn wait(&mut self, at: u64) {
+        embassy_time::Timer::at(Instant::from_micros(at)).await;
+    }
+}
+
+/// A received frame, as buffered by [`ChannelRadio`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxFrame {
+    /// The PSDU of the received frame
+    pub psdu: heapless::Vec<u8, PSDU_LEN>,
+    /// The meta-data associated with the received frame
+    pub meta: PsduMeta,
+}
+
+/// An adapter that - rather than requiring the application to call `receive` - continuously
+/// receives frames with the wrapped radio and pushes them into a bounded `embassy_sync` channel.
+///
+/// This is an alternative, push-based consumption model to the pull-based [`Radio::receive`]:
+/// frame arrival (driven by `ChannelRadio::run`, possibly in a separate, higher-priority
+/// executor or on another core) is decoupled from frame processing, which happens in whichever
+/// task(s) consume the channel via [`ChannelRadio::receiver`].
+///
+/// The channel is bounded. If it is full when a new frame arrives, the new frame is dropped
+/// (and counted, see [`ChannelRadio::dropped`]), so a slow consumer naturally applies
+/// backpressure by losing the newest frames rather than stalling the radio.
+///
+/// Note that while `run` is in progress, the radio is exclusively used for receiving.
+pub struct ChannelRadio<'a, R, M, const N: usize>
+where
+    M: RawMutex,
+{
+    /// The wrapped radio.
+    radio: R,
+    /// The channel to push the received frames into.
+    channel: &'a embassy_sync::channel::Channel<M, RxFrame, N>,
+    /// The number of frames dropped because the channel was full.
+    dropped: u32,
+}
+
+impl<'a, R, M, const N: usize> ChannelRadio<'a, R, M, N>
+where
+    R: Radio,
+    M: RawMutex,
+{
+    /// Create a new `ChannelRadio`.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to receive with.
+    /// - `channel`: The channel to push the received frames into.
+    pub const fn new(radio: R, channel: &'a embassy_sync::channel::Channel<M, RxFrame, N>) -> Self {
+        Self {
+            radio,
+            channel,
+            dropped: 0,
+        }
+    }
+
+    /// Return a receiver for the channel the received frames are pushed into.
+    pub fn receiver(&self) -> embassy_sync::channel::Receiver<'a, M, RxFrame, N> {
+        self.channel.receiver()
+    }
+
+    /// Return the number of frames dropped so far because the channel was full.
+    ///
+    /// The counter wraps around on overflow.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Return the wrapped radio.
+    pub fn release(self) -> R {
+        self.radio
+    }
+
+    /// Continuously receive frames and push them into the channel.
+    ///
+    /// Receive errors are logged and otherwise ignored.
+    pub async fn run(&mut self) -> ! {
+        let mut psdu_buf = [0_u8; PSDU_LEN];
+
+        debug!("ChannelRadio, running");
+
+        loop {
+            let psdu_meta = match self.radio.receive(&mut psdu_buf).await {
+                Ok(psdu_meta) => psdu_meta,
+                Err(e) => {
+                    warn!("ChannelRadio, receive failed: {:?}", e.kind());
+                    continue;
+                }
+            };
+
+            let mut frame = RxFrame {
+                psdu: heapless::Vec::new(),
+                meta: psdu_meta,
+            };
+            unwrap!(frame.psdu.extend_from_slice(&psdu_buf[..psdu_meta.len]));
+
+            if self.channel.try_send(frame).is_err() {
+                self.dropped = self.dropped.wrapping_add(1);
+
+                warn!(
+                    "ChannelRadio, channel full, frame dropped (total dropped: {})",
+                    self.dropped
+                );
+            }
+        }
+    }
+}
+
+/// A fixed pool of `N` receive buffers of `LEN` bytes each, for [`RadioExt::receive_pooled`].
+///
+/// Gives a predictable memory usage when several received frames need to be held at the same
+/// time: a buffer is taken from the pool for each received frame and goes back to the pool
+/// when the [`PooledFrame`] is dropped. When the pool is exhausted, `receive_pooled` waits until
+/// a frame is dropped, so a consumer holding on to too many frames stalls the receiving.
+///
+/// The pool is meant to serve a single receiving task; with several tasks waiting for a buffer
+/// at the same time, only one of them is woken up when a buffer is released.
+pub struct RxPool<const N: usize, const LEN: usize = PSDU_LEN> {
+    /// The buffers
+    bufs: UnsafeCell<[[u8; LEN]; N]>,
+    /// Which buffers are currently in use
+    used: Mutex<CriticalSectionRawMutex, RefCell<[bool; N]>>,
+    /// Signalled whenever a buffer is released
+    released: Signal<CriticalSectionRawMutex, ()>,
+}
+
+impl<const N: usize, const LEN: usize> RxPool<N, LEN> {
+    /// Create a new pool.
+    pub const fn new() -> Self {
+        Self {
+            bufs: UnsafeCell::new([[0; LEN]; N]),
+            used: Mutex::new(RefCell::new([false; N])),
+            released: Signal::new(),
+        }
+    }
+
+    /// Return the number of buffers currently not in use.
+    pub fn available(&self) -> usize {
+        self.used
+            .lock(|used| used.borrow().iter().filter(|used| !**used).count())
+    }
+
+    /// Take a buffer from the pool, waiting for one to be released if all are in use.
+    async fn alloc(&self) -> PoolBuf<'_, N, LEN> {
+        loop {
+            self.released.reset();
+
+            let index = self.used.lock(|used| {
+                let mut used = used.borrow_mut();
+
+                let index = used.iter().position(|used| !*used)?;
+                used[index] = true;
+
+                Some(index)
+            });
+
+            if let Some(index) = index {
+                break PoolBuf { pool: self, index };
+            }
+
+            trace!("RxPool, exhausted, waiting for a buffer to be released");
+
+            self.released.wait().await;
+        }
+    }
+
+    /// Return a buffer to the pool.
+    fn release(&self, index: usize) {
+        self.used.lock(|used| used.borrow_mut()[index] = false);
+        self.released.signal(());
+    }
+}
+
+impl<const N: usize, const LEN: usize> Default for RxPool<N, LEN> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// A high-res timer trait that is necessary for the `MacRadio` to send ACKs
-/// at the right time.
-///
-/// The timer should have a microsecond, or a few microseconds' resolution.
-pub trait MacRadioTimer {
-    /// Returns the current time - in microseconds - from some predefined period in time
-    /// The returned time should be monotonic.
-    fn now(&mut self) -> u64;
+// Safe because each buffer is only ever accessed via the single `PoolBuf` owning it,
+// and the ownership is tracked in `used`, which is protected by a mutex
+unsafe impl<const N: usize, const LEN: usize> Sync for RxPool<N, LEN> {}
 
-    /// Waits until the current time becomes equal or greater than the given time.
-    ///
-    /// If the current time is already equal or greater than the given time,
-    /// the function should return immediately, without waiting.
-    ///
-    /// Arguments:
-    /// - `time`: The time - in microseconds - to wait until.
-    async fn wait(&mut self, at: u64);
+/// A buffer taken from an `RxPool`, returned to the pool on drop.
+struct PoolBuf<'p, const N: usize, const LEN: usize> {
+    pool: &'p RxPool<N, LEN>,
+    index: usize,
 }
 
-impl<T> MacRadioTimer for &mut T
-where
-    T: MacRadioTimer,
-{
-    fn now(&mut self) -> u64 {
-        T::now(self)
+impl<const N: usize, const LEN: usize> PoolBuf<'_, N, LEN> {
+    fn as_ref(&self) -> &[u8] {
+        // Safe because this is the only `PoolBuf` for this index, see `RxPool`
+        unsafe { &(*self.pool.bufs.get())[self.index] }
     }
 
-    async fn wait(&mut self, at: u64) {
-        T::wait(self, at).await
+    fn as_mut(&mut self) -> &mut [u8] {
+        // Safe because this is the only `PoolBuf` for this index, see `RxPool`
+        unsafe { &mut (*self.pool.bufs.get())[self.index] }
     }
 }
 
-/// An implementation of `MacRadioTimer` that uses the `embassy_time` crate.
+impl<const N: usize, const LEN: usize> Drop for PoolBuf<'_, N, LEN> {
+    fn drop(&mut self) {
+        self.pool.release(self.index);
+    }
+}
+
+/// A frame received with [`RadioExt::receive_pooled`].
 ///
-/// Note that this implementation might NOT be appropriate when the concrete
-/// `embassy-time` implementation is not having a high-enough resolution.
-pub struct EmbassyTimeTimer;
+/// Holds on to its [`RxPool`] buffer until dropped.
+pub struct PooledFrame<'p, const N: usize, const LEN: usize> {
+    buf: PoolBuf<'p, N, LEN>,
+    meta: PsduMeta,
+}
 
-impl MacRadioTimer for EmbassyTimeTimer {
-    fn now(&mut self) -> u64 {
-        Instant::now().as_micros()
+impl<const N: usize, const LEN: usize> PooledFrame<'_, N, LEN> {
+    /// Return the PSDU of the received frame.
+    pub fn psdu(&self) -> &[u8] {
+        &self.buf.as_ref()[..self.meta.len]
     }
 
-    async fn wait(&mut self, at: u64) {
-        embassy_time::Timer::at(Instant::from_micros(at)).await;
+    /// Return the meta-data associated with the received frame.
+    pub fn meta(&self) -> &PsduMeta {
+        &self.meta
     }
 }
 
@@ -781,6 +2560,10 @@ impl<const CAPS: otRadioCaps> Radio for ProxyRadio<'_, CAPS> {
         Ok(())
     }
 
+    fn is_promiscuous(&self) -> bool {
+        self.config.is_promiscuous()
+    }
+
     async fn transmit(
         &mut self,
         psdu: &[u8],
@@ -822,7 +2605,9 @@ impl<const CAPS: otRadioCaps> Radio for ProxyRadio<'_, CAPS> {
         let psdu_meta = (ack_psdu_buf.is_some() && !resp.psdu.is_empty()).then_some(PsduMeta {
             len: resp.psdu.len(),
             channel: resp.psdu_channel,
+            channel_page: resp.psdu_channel_page,
             rssi: resp.psdu_rssi,
+            timestamp: resp.psdu_timestamp,
         });
 
         if let Some(ack_psdu_buf) = ack_psdu_buf {
@@ -882,7 +2667,9 @@ impl<const CAPS: otRadioCaps> Radio for ProxyRadio<'_, CAPS> {
                 let psdu_meta = PsduMeta {
                     len,
                     channel: resp.psdu_channel,
+                    channel_page: resp.psdu_channel_page,
                     rssi: resp.psdu_rssi,
+                    timestamp: resp.psdu_timestamp,
                 };
 
                 self.cancelled.set(false);
@@ -942,7 +2729,9 @@ impl PhyRadioRunner<'_> {
                     response.psdu.clear();
                     response.result = Err(RadioErrorKind::Other);
                     response.psdu_channel = 0;
+                    response.psdu_channel_page = 0;
                     response.psdu_rssi = None;
+                    response.psdu_timestamp = None;
 
                     self.response.send_done();
 
@@ -973,7 +2762,9 @@ impl PhyRadioRunner<'_> {
 
         response.psdu.clear();
         response.psdu_channel = 0;
+        response.psdu_channel_page = 0;
         response.psdu_rssi = None;
+        response.psdu_timestamp = None;
 
         trace!("PhyRadioRunner, processing request: {:?}", request);
 
@@ -1009,7 +2800,9 @@ impl PhyRadioRunner<'_> {
             if let Ok(Some(psdu_meta)) = &result {
                 response.psdu.truncate(psdu_meta.len);
                 response.psdu_channel = psdu_meta.channel;
+                response.psdu_channel_page = psdu_meta.channel_page;
                 response.psdu_rssi = psdu_meta.rssi;
+                response.psdu_timestamp = psdu_meta.timestamp;
             } else {
                 // No frame returned, so clear the response fields
                 response.psdu.clear();
@@ -1141,10 +2934,16 @@ struct ProxyRadioResponse {
     /// - For TX: the channel on which the ACK frame was received
     /// - For RX: the channel on which the regular frame was received
     psdu_channel: u8,
+    /// The channel page of `psdu_channel`
+    psdu_channel_page: u8,
     /// The RSSI of the received frame, if the radio supports appending it at the end of the frame:
     /// - For TX: the RSSI of the received ACK frame
     /// - For RX: the RSSI of the received frame
     psdu_rssi: Option<i8>,
+    /// The timestamp of the received frame, if the radio supports timestamping:
+    /// - For TX: the timestamp of the received ACK frame
+    /// - For RX: the timestamp of the received frame
+    psdu_timestamp: Option<u64>,
 }
 
 impl ProxyRadioResponse {
@@ -1154,14 +2953,93 @@ impl ProxyRadioResponse {
             result: Ok(()),
             psdu: heapless::Vec::new(),
             psdu_channel: 0,
+            psdu_channel_page: 0,
             psdu_rssi: None,
+            psdu_timestamp: None,
         }
     }
 }
 
+/// The timings of the IEEE 802.15.4 2.4 GHz O-QPSK PHY (channel page 0), for airtime estimates.
+pub(crate) mod phy {
+    use super::mac::MacHeader;
+
+    /// The bit rate, in bits per second.
+    pub const BIT_RATE: u32 = 250_000;
+    /// The duration of an octet, in microseconds.
+    pub const OCTET_US: u32 = 8 * 1_000_000 / BIT_RATE;
+    /// `phySymbolsPerOctet`.
+    pub const SYMBOLS_PER_OCTET: u32 = 2;
+    /// The duration of a symbol, in microseconds.
+    pub const SYMBOL_US: u32 = OCTET_US / SYMBOLS_PER_OCTET;
+    /// The length of the synchronization header (preamble + SFD) and the PHY header, in octets.
+    pub const SHR_PHR_LEN: u32 = 4 + 1 + 1;
+    /// `aMaxPhyPacketSize`, i.e. the maximum length of a PSDU.
+    pub const MAX_PSDU_LEN: u32 = 127;
+    /// `phySHRDuration`, in symbols.
+    pub const SHR_DURATION_SYMBOLS: u32 = 10;
+    /// `aTurnaroundTime` (RX-to-TX and TX-to-RX), in symbols.
+    pub const TURNAROUND_SYMBOLS: u32 = 12;
+    /// `aCcaTime`, in symbols.
+    pub const CCA_SYMBOLS: u32 = 8;
+    /// `aUnitBackoffPeriod`, in symbols.
+    pub const UNIT_BACKOFF_PERIOD_SYMBOLS: u32 = 20;
+    /// `macMinLIFSPeriod`, the minimum spacing after a frame longer than `aMaxSIFSFrameSize`,
+    /// in symbols.
+    pub const MIN_LIFS_PERIOD_SYMBOLS: u32 = 40;
+    /// `macAckWaitDuration`
+    /// (`aUnitBackoffPeriod + aTurnaroundTime + phySHRDuration + 6 * phySymbolsPerOctet`),
+    /// i.e. for how long a transmitter waits for an ACK after the end of its frame, in symbols.
+    pub const ACK_WAIT_SYMBOLS: u32 = UNIT_BACKOFF_PERIOD_SYMBOLS
+        + TURNAROUND_SYMBOLS
+        + SHR_DURATION_SYMBOLS
+        + 6 * SYMBOLS_PER_OCTET;
+
+    /// Return the airtime, in microseconds, of a frame with a PSDU of `psdu_len` octets.
+    pub const fn airtime_us(psdu_len: u32) -> u32 {
+        (SHR_PHR_LEN + psdu_len) * OCTET_US
+    }
+
+    /// Return the theoretical maximum rate, in frames per second, of back-to-back
+    /// maximum-size frames, as a `(tx_fps, rx_fps)` tuple.
+    ///
+    /// Each frame takes its airtime, followed by the spacing before the next frame: the longer
+    /// of `macMinLIFSPeriod` and the CCA plus RX-to-TX turnaround of the next frame. If `acked`
+    /// is `true`, each frame also waits for its ACK before that spacing: the transmitter for up
+    /// to `macAckWaitDuration`, the receiver for the turnaround plus the airtime of the Imm-ACK
+    /// it sends.
+    ///
+    /// No processing overhead is accounted for.
+    pub const fn max_frame_rate(acked: bool) -> (u32, u32) {
+        let spacing_us = if MIN_LIFS_PERIOD_SYMBOLS > CCA_SYMBOLS + TURNAROUND_SYMBOLS {
+            MIN_LIFS_PERIOD_SYMBOLS * SYMBOL_US
+        } else {
+            (CCA_SYMBOLS + TURNAROUND_SYMBOLS) * SYMBOL_US
+        };
+
+        let (tx_ack_us, rx_ack_us) = if acked {
+            (
+                ACK_WAIT_SYMBOLS * SYMBOL_US,
+                TURNAROUND_SYMBOLS * SYMBOL_US + airtime_us(MacHeader::ACK_PSDU_LEN as _),
+            )
+        } else {
+            (0, 0)
+        };
+
+        let frame_us = airtime_us(MAX_PSDU_LEN) + spacing_us;
+
+        (
+            1_000_000 / (frame_us + tx_ack_us),
+            1_000_000 / (frame_us + rx_ack_us),
+        )
+    }
+}
+
 /// A minimal set of utilities for parsing the IEEE 802.15.4 MAC header
 /// for the purposes of MAC filtering and RX/TX ACK processing.
-mod mac {
+pub(crate) mod mac {
+    use crate::MacAddr;
+
     /// A parsed IEEE 802.15.4 MAC header.
     pub struct MacHeader {
         /// Frame Control Field (FCF)
@@ -1207,16 +3085,16 @@ mod mac {
         #[allow(unused)]
         const FCF_PENDING_BIT: u16 = 1 << 4;
         const FCF_ACK_REQ_BIT: u16 = 1 << 5;
-        #[allow(unused)]
         const FCF_PAN_ID_COMPRESSION_MASK: u16 = 1 << 6;
+        const FCF_SEQ_SUPPRESSION_BIT: u16 = 1 << 8;
+        const FCF_IE_PRESENT_BIT: u16 = 1 << 9;
         const FCF_FRAME_DST_ADDR_MODE_SHIFT: u16 = 10;
         const FCF_FRAME_DST_ADDR_MODE_MASK: u16 = 0x03 << Self::FCF_FRAME_DST_ADDR_MODE_SHIFT;
         const FCF_FRAME_VERSION_SHIFT: u16 = 12;
         const FCF_FRAME_VERSION_MASK: u16 = 0x03 << Self::FCF_FRAME_VERSION_SHIFT;
-        #[allow(unused)]
+        const FRAME_VERSION_2015: u16 = 2;
         const FCF_FRAME_SRC_ADDR_MODE_SHIFT: u16 = 14;
-        #[allow(unused)]
-        const FCF_FRAME_SRC_ADDR_MODE_MASK: u16 = 0x03 << Self::FCF_FRAME_DST_ADDR_MODE_SHIFT;
+        const FCF_FRAME_SRC_ADDR_MODE_MASK: u16 = 0x03 << Self::FCF_FRAME_SRC_ADDR_MODE_SHIFT;
 
         /// Create a new empty MAC header.
         pub const fn new() -> Self {
@@ -1272,6 +3150,90 @@ mod mac {
             Some(())
         }
 
+        /// Return the source address of the frame with the given PSDU.
+        /// Assumes that the MAC header was successfully loaded from the same PSDU.
+        ///
+        /// Returns `None` if the frame does not contain a source address,
+        /// or if the source address mode is unknown (reserved) or the PSDU is too short.
+        #[inline(always)]
+        pub fn src_addr(&self, psdu: &[u8]) -> Option<MacAddr> {
+            let mut offset = Self::ADDRS_OFFSET
+                + match FrameAddrMode::get_dst(self.fcf)? {
+                    FrameAddrMode::NotPresent => 0,
+                    FrameAddrMode::Short => 2 + 2,
+                    FrameAddrMode::Extended => 2 + 8,
+                };
+
+            let src_addr_mode = FrameAddrMode::get_src(self.fcf)?;
+
+            if !matches!(src_addr_mode, FrameAddrMode::NotPresent)
+                && (self.fcf & Self::FCF_PAN_ID_COMPRESSION_MASK) == 0
+            {
+                // Source PAN ID is present
+                offset += 2;
+            }
+
+            match src_addr_mode {
+                FrameAddrMode::NotPresent => None,
+                FrameAddrMode::Short => {
+                    Self::ensure_len(psdu, offset + 2 + Self::CRC_LEN)?;
+
+                    Some(MacAddr::Short(u16::from_le_bytes(unwrap!(psdu
+                        [offset..offset + 2]
+                        .try_into()))))
+                }
+                FrameAddrMode::Extended => {
+                    Self::ensure_len(psdu, offset + 8 + Self::CRC_LEN)?;
+
+                    Some(MacAddr::Ext(u64::from_le_bytes(unwrap!(psdu
+                        [offset..offset + 8]
+                        .try_into()))))
+                }
+            }
+        }
+
+        /// Return `true` if the PSDU is long enough to contain an FCF, and the FCF
+        /// denotes a supported (i.e. non-reserved) frame type: beacon, data, ACK or MAC command.
+        #[inline(always)]
+        pub fn has_supported_frame_type(psdu: &[u8]) -> bool {
+            if Self::ensure_len(psdu, Self::SEQ_OFFSET).is_none() {
+                return false;
+            }
+
+            let fcf =
+                u16::from_le_bytes(unwrap!(psdu[Self::FCF_OFFSET..Self::SEQ_OFFSET].try_into()));
+
+            FrameType::get(fcf).is_some()
+        }
+
+        /// Return the frame-pending bit of the frame with the given PSDU,
+        /// or `None` if the PSDU is too short to contain an FCF.
+        ///
+        /// Works for any frame version, so in particular for both Imm-ACKs and Enh-ACKs.
+        #[inline(always)]
+        pub fn frame_pending(psdu: &[u8]) -> Option<bool> {
+            Self::ensure_len(psdu, Self::SEQ_OFFSET)?;
+
+            let fcf =
+                u16::from_le_bytes(unwrap!(psdu[Self::FCF_OFFSET..Self::SEQ_OFFSET].try_into()));
+
+            Some((fcf & Self::FCF_PENDING_BIT) != 0)
+        }
+
+        /// Return the ACK-request bit of the frame with the given PSDU,
+        /// or `None` if the PSDU is too short to contain an FCF.
+        ///
+        /// Works for any frame version.
+        #[inline(always)]
+        pub fn ack_requested(psdu: &[u8]) -> Option<bool> {
+            Self::ensure_len(psdu, Self::SEQ_OFFSET)?;
+
+            let fcf =
+                u16::from_le_bytes(unwrap!(psdu[Self::FCF_OFFSET..Self::SEQ_OFFSET].try_into()));
+
+            Some((fcf & Self::FCF_ACK_REQ_BIT) != 0)
+        }
+
         /// Return `true` if the frame needs an ACK.
         #[inline(always)]
         pub fn needs_ack(&self) -> bool {
@@ -1298,6 +3260,106 @@ mod mac {
             Self::ACK_PSDU_LEN
         }
 
+        /// Prepare an Enh-ACK PSDU for the frame with the given PSDU, if the frame is an ACK-requesting
+        /// frame-version-2 (IEEE 802.15.4-2015) frame, including the provided (already encoded)
+        /// header IEs.
+        ///
+        /// The Enh-ACK is addressed to the source address of the frame (without a PAN ID and
+        /// a source address), and is not secured.
+        ///
+        /// Returns the length of the Enh-ACK PSDU, or `None` if the frame does not need an Enh-ACK,
+        /// or if its header is malformed or the Enh-ACK would not fit in `ack_buf`.
+        pub fn prep_enh_ack(psdu: &[u8], header_ies: &[u8], ack_buf: &mut [u8]) -> Option<usize> {
+            Self::ensure_len(psdu, Self::SEQ_OFFSET + Self::CRC_LEN)?;
+
+            let fcf =
+                u16::from_le_bytes(unwrap!(psdu[Self::FCF_OFFSET..Self::SEQ_OFFSET].try_into()));
+
+            if (fcf & Self::FCF_FRAME_VERSION_MASK) >> Self::FCF_FRAME_VERSION_SHIFT
+                != Self::FRAME_VERSION_2015
+                || (fcf & Self::FCF_ACK_REQ_BIT) == 0
+                || matches!(FrameType::get(fcf)?, FrameType::Ack)
+            {
+                return None;
+            }
+
+            let seq_suppressed = (fcf & Self::FCF_SEQ_SUPPRESSION_BIT) != 0;
+            let pan_id_compression = (fcf & Self::FCF_PAN_ID_COMPRESSION_MASK) != 0;
+
+            let dst_addr_mode = FrameAddrMode::get_dst(fcf)?;
+            let src_addr_mode = FrameAddrMode::get_src(fcf)?;
+
+            // PAN ID presence as per IEEE 802.15.4-2015, Table 7-2
+            let (dst_pan_id, src_pan_id) = match (&dst_addr_mode, &src_addr_mode) {
+                (FrameAddrMode::NotPresent, FrameAddrMode::NotPresent) => {
+                    (pan_id_compression, false)
+                }
+                (_, FrameAddrMode::NotPresent) => (!pan_id_compression, false),
+                (FrameAddrMode::NotPresent, _) => (false, !pan_id_compression),
+                (FrameAddrMode::Extended, FrameAddrMode::Extended) => (!pan_id_compression, false),
+                _ => (true, !pan_id_compression),
+            };
+
+            let mut offset = Self::SEQ_OFFSET + if seq_suppressed { 0 } else { Self::SEQ_LEN };
+
+            offset += if dst_pan_id { 2 } else { 0 } + dst_addr_mode.addr_len();
+            offset += if src_pan_id { 2 } else { 0 };
+
+            let src_addr_len = src_addr_mode.addr_len();
+            Self::ensure_len(psdu, offset + src_addr_len + Self::CRC_LEN)?;
+
+            let src_addr = &psdu[offset..offset + src_addr_len];
+
+            let ack_len = Self::SEQ_OFFSET
+                + if seq_suppressed { 0 } else { Self::SEQ_LEN }
+                + src_addr_len
+                + header_ies.len()
+                + Self::CRC_LEN;
+
+            if ack_buf.len() < ack_len {
+                return None;
+            }
+
+            // The Enh-ACK destination is the frame source, with no PAN ID (i.e. PAN ID compression
+            // set if there is a destination address) and no source address
+            let mut ack_fcf = Self::FCF_FRAME_TYPE_ACK
+                | (Self::FRAME_VERSION_2015 << Self::FCF_FRAME_VERSION_SHIFT)
+                | (fcf & Self::FCF_FRAME_SRC_ADDR_MODE_MASK)
+                    >> (Self::FCF_FRAME_SRC_ADDR_MODE_SHIFT - Self::FCF_FRAME_DST_ADDR_MODE_SHIFT);
+
+            if src_addr_len > 0 {
+                ack_fcf |= Self::FCF_PAN_ID_COMPRESSION_MASK;
+            }
+
+            if seq_suppressed {
+                ack_fcf |= Self::FCF_SEQ_SUPPRESSION_BIT;
+            }
+
+            if !header_ies.is_empty() {
+                ack_fcf |= Self::FCF_IE_PRESENT_BIT;
+            }
+
+            ack_buf[..Self::FCF_LEN].copy_from_slice(&ack_fcf.to_le_bytes());
+
+            let mut ack_offset = Self::SEQ_OFFSET;
+
+            if !seq_suppressed {
+                ack_buf[ack_offset] = psdu[Self::SEQ_OFFSET];
+                ack_offset += Self::SEQ_LEN;
+            }
+
+            ack_buf[ack_offset..ack_offset + src_addr_len].copy_from_slice(src_addr);
+            ack_offset += src_addr_len;
+
+            ack_buf[ack_offset..ack_offset + header_ies.len()].copy_from_slice(header_ies);
+            ack_offset += header_ies.len();
+
+            // CRC, will be filled-in by the PHY driver
+            ack_buf[ack_offset..ack_offset + Self::CRC_LEN].fill(0);
+
+            Some(ack_len)
+        }
+
         /// Return `true` if the frame is an ACK frame and is an ACK for the given source sequence number.
         #[inline(always)]
         pub fn ack_for(&self, src_seq: u8) -> bool {
@@ -1382,5 +3444,556 @@ mod mac {
                 _ => None,
             }
         }
+
+        /// Return the length of an address in this mode.
+        #[inline(always)]
+        fn addr_len(&self) -> usize {
+            match self {
+                Self::NotPresent => 0,
+                Self::Short => 2,
+                Self::Extended => 8,
+            }
+        }
+
+        /// Get the source address mode from the FCF.
+        ///
+        /// If the mode is not supported, returns `None`.
+        #[inline(always)]
+        fn get_src(fcf: u16) -> Option<Self> {
+            match (fcf & MacHeader::FCF_FRAME_SRC_ADDR_MODE_MASK)
+                >> MacHeader::FCF_FRAME_SRC_ADDR_MODE_SHIFT
+            {
+                0 => Some(Self::NotPresent),
+                2 => Some(Self::Short),
+                3 => Some(Self::Extended),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_futures::block_on;
+
+    use super::*;
+
+    // Frame Control Field bits, as per IEEE 802.15.4-2006, 7.2.1.1
+    const FCF_DATA: u16 = 0x0001;
+    const FCF_ACK: u16 = 0x0002;
+    const FCF_FRAME_PENDING: u16 = 1 << 4;
+    const FCF_ACK_REQ: u16 = 1 << 5;
+    const FCF_PAN_ID_COMPRESSION: u16 = 1 << 6;
+    const FCF_SEQ_SUPPRESSION: u16 = 1 << 8;
+    const FCF_IE_PRESENT: u16 = 1 << 9;
+    const FCF_DST_SHORT: u16 = 2 << 10;
+    const FCF_SRC_SHORT: u16 = 2 << 14;
+    const FCF_SRC_EXT: u16 = 3 << 14;
+    const FCF_VERSION_2006: u16 = 1 << 12;
+    const FCF_VERSION_2015: u16 = 2 << 12;
+
+    /// A data frame with short destination and source addresses and a compressed PAN ID.
+    const FCF_DATA_SHORT: u16 =
+        FCF_DATA | FCF_PAN_ID_COMPRESSION | FCF_DST_SHORT | FCF_SRC_SHORT | FCF_VERSION_2006;
+
+    /// Build a PSDU with the given FCF, sequence number and addressing fields,
+    /// followed by a one-byte payload and the (zeroed) FCS.
+    fn frame(fcf: u16, seq: u8, addrs: &[&[u8]]) -> heapless::Vec<u8, 127> {
+        let mut psdu = heapless::Vec::new();
+
+        psdu.extend_from_slice(&fcf.to_le_bytes()).unwrap();
+        psdu.push(seq).unwrap();
+
+        for addr in addrs {
+            psdu.extend_from_slice(addr).unwrap();
+        }
+
+        psdu.extend_from_slice(&[0xaa, 0, 0]).unwrap();
+
+        psdu
+    }
+
+    /// Build a data frame from the short address `src` to the short address `dst` in PAN `pan_id`.
+    fn data_frame(pan_id: u16, dst: u16, src: u16, seq: u8) -> heapless::Vec<u8, 127> {
+        frame(
+            FCF_DATA_SHORT,
+            seq,
+            &[
+                &pan_id.to_le_bytes(),
+                &dst.to_le_bytes(),
+                &src.to_le_bytes(),
+            ],
+        )
+    }
+
+    /// A radio which delivers the given frames in order, and then fails with
+    /// `RadioErrorKind::RxFailed`.
+    struct TestRadio<'a> {
+        frames: &'a [&'a [u8]],
+    }
+
+    impl Radio for TestRadio<'_> {
+        type Error = RadioErrorKind;
+
+        const CAPS: Capabilities = Capabilities::empty();
+
+        const MAC_CAPS: MacCapabilities = MacCapabilities::empty();
+
+        async fn set_config(&mut self, _config: &Config) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn transmit(
+            &mut self,
+            _psdu: &[u8],
+            _cca: bool,
+            _ack_psdu_buf: Option<&mut [u8]>,
+        ) -> Result<Option<PsduMeta>, Self::Error> {
+            Ok(None)
+        }
+
+        async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+            let (frame, frames) = self.frames.split_first().ok_or(RadioErrorKind::RxFailed)?;
+            self.frames = frames;
+
+            psdu_buf[..frame.len()].copy_from_slice(frame);
+
+            Ok(PsduMeta {
+                len: frame.len(),
+                channel: 11,
+                channel_page: 0,
+                rssi: None,
+                timestamp: None,
+            })
+        }
+    }
+
+    #[test]
+    fn rx_filter_empty() {
+        let filter = RxFilter::new();
+
+        assert!(filter.is_empty());
+        assert!(filter.matches(&data_frame(0x1234, 0x0001, 0x0002, 0)));
+        // Even frames which cannot be parsed pass an empty filter
+        assert!(filter.matches(&[0xff]));
+    }
+
+    #[test]
+    fn rx_filter_pan_id() {
+        let filter = RxFilter {
+            pan_id: Some(0x1234),
+            ..RxFilter::new()
+        };
+
+        assert!(filter.matches(&data_frame(0x1234, 0x0001, 0x0002, 0)));
+        assert!(filter.matches(&data_frame(MacHeader::BROADCAST_PAN_ID, 0x0001, 0x0002, 0)));
+        assert!(!filter.matches(&data_frame(0x4321, 0x0001, 0x0002, 0)));
+        assert!(!filter.matches(&[0x61, 0x98]));
+    }
+
+    #[test]
+    fn receive_filtered_skips_other_pans() {
+        let other_pan = data_frame(0x4321, 0x0001, 0x0002, 1);
+        let own_pan = data_frame(0x1234, 0x0001, 0x0002, 2);
+
+        let mut radio = TestRadio {
+            frames: &[&other_pan, &own_pan],
+        };
+
+        let filter = RxFilter {
+            pan_id: Some(0x1234),
+            ..RxFilter::new()
+        };
+
+        let mut psdu_buf = [0; 127];
+        let psdu_meta = block_on(radio.receive_filtered(&mut psdu_buf, &filter)).unwrap();
+
+        assert_eq!(&psdu_buf[..psdu_meta.len], own_pan.as_slice());
+        assert_eq!(
+            block_on(radio.receive_filtered(&mut psdu_buf, &filter)),
+            Err(RadioErrorKind::RxFailed)
+        );
+    }
+
+    #[test]
+    fn reserved_frame_types() {
+        // Beacon, data, ACK and MAC command frames are supported
+        for frame_type in 0..=3 {
+            assert!(MacHeader::has_supported_frame_type(&frame(
+                frame_type | FCF_VERSION_2006,
+                0,
+                &[]
+            )));
+        }
+
+        // The reserved types (multipurpose, fragment and extended in 802.15.4-2015) are not
+        for frame_type in 4..=7 {
+            assert!(!MacHeader::has_supported_frame_type(&frame(
+                frame_type | FCF_VERSION_2006,
+                0,
+                &[]
+            )));
+        }
+
+        assert!(!MacHeader::has_supported_frame_type(&[FCF_DATA as u8]));
+    }
+
+    #[test]
+    fn retryable_error_kinds() {
+        for kind in [
+            RadioErrorKind::RxInvalid,
+            RadioErrorKind::RxFailed,
+            RadioErrorKind::TxFailed,
+            RadioErrorKind::TxAckFailed,
+            RadioErrorKind::RxAckFailed,
+            RadioErrorKind::TxAckTimeout,
+            RadioErrorKind::RxAckTimeout,
+            RadioErrorKind::RxAckInvalid,
+            RadioErrorKind::Busy,
+            RadioErrorKind::RateLimited,
+            RadioErrorKind::Other,
+        ] {
+            assert!(kind.is_retryable(), "{kind:?} should be retryable");
+        }
+
+        for kind in [
+            RadioErrorKind::TxInvalid,
+            RadioErrorKind::InvalidFrame,
+            RadioErrorKind::DeadlineMissed,
+            RadioErrorKind::Silenced,
+            RadioErrorKind::ConfigUnsupported,
+            RadioErrorKind::BufferTooSmall,
+        ] {
+            assert!(!kind.is_retryable(), "{kind:?} should be fatal");
+        }
+    }
+
+    #[test]
+    fn src_addr() {
+        let mut header = MacHeader::new();
+
+        let psdu = data_frame(0x1234, 0x0001, 0x0002, 0);
+        assert_eq!(header.load(&psdu), Some(()));
+        assert_eq!(header.src_addr(&psdu), Some(MacAddr::Short(0x0002)));
+
+        // Without PAN ID compression, the source PAN ID precedes the source address
+        let psdu = frame(
+            FCF_DATA | FCF_DST_SHORT | FCF_SRC_EXT | FCF_VERSION_2006,
+            0,
+            &[
+                &0x1234_u16.to_le_bytes(),
+                &0x0001_u16.to_le_bytes(),
+                &0x4321_u16.to_le_bytes(),
+                &0x0011_2233_4455_6677_u64.to_le_bytes(),
+            ],
+        );
+        assert_eq!(header.load(&psdu), Some(()));
+        assert_eq!(
+            header.src_addr(&psdu),
+            Some(MacAddr::Ext(0x0011_2233_4455_6677))
+        );
+
+        let psdu = frame(
+            FCF_DATA | FCF_DST_SHORT | FCF_VERSION_2006,
+            0,
+            &[&0x1234_u16.to_le_bytes(), &0x0001_u16.to_le_bytes()],
+        );
+        assert_eq!(header.load(&psdu), Some(()));
+        assert_eq!(header.src_addr(&psdu), None);
+
+        // Too short for a source address after the destination address and the FCS
+        let psdu = data_frame(0x1234, 0x0001, 0x0002, 0);
+        let psdu = &psdu[..9];
+        assert_eq!(header.load(psdu), Some(()));
+        assert_eq!(header.src_addr(psdu), None);
+    }
+
+    #[test]
+    fn rx_filter_coordinator_addr() {
+        let filter = RxFilter {
+            coordinator_addr: Some(MacAddr::Short(0x0002)),
+            ..RxFilter::new()
+        };
+
+        assert!(filter.matches(&data_frame(0x1234, 0x0001, 0x0002, 0)));
+        assert!(!filter.matches(&data_frame(0x1234, 0x0001, 0x0003, 0)));
+        // Frames without a source address
+        assert!(!filter.matches(&frame(
+            FCF_DATA | FCF_DST_SHORT | FCF_VERSION_2006,
+            0,
+            &[&0x1234_u16.to_le_bytes(), &0x0001_u16.to_le_bytes()],
+        )));
+
+        let filter = RxFilter {
+            pan_id: Some(0x1234),
+            coordinator_addr: Some(MacAddr::Short(0x0002)),
+        };
+
+        assert!(filter.matches(&data_frame(0x1234, 0x0001, 0x0002, 0)));
+        assert!(!filter.matches(&data_frame(0x4321, 0x0001, 0x0002, 0)));
+    }
+
+    #[test]
+    fn ack_requested() {
+        let psdu = data_frame(0x1234, 0x0001, 0x0002, 0);
+        assert_eq!(MacHeader::ack_requested(&psdu), Some(false));
+
+        let psdu = frame(
+            FCF_DATA_SHORT | FCF_ACK_REQ,
+            0,
+            &[
+                &0x1234_u16.to_le_bytes(),
+                &0x0001_u16.to_le_bytes(),
+                &0x0002_u16.to_le_bytes(),
+            ],
+        );
+        assert_eq!(MacHeader::ack_requested(&psdu), Some(true));
+
+        assert_eq!(MacHeader::ack_requested(&psdu[..1]), None);
+    }
+
+    /// Build an Imm-ACK PSDU.
+    fn imm_ack(fcf: u16, seq: u8) -> [u8; MacHeader::ACK_PSDU_LEN] {
+        let fcf = (FCF_ACK | fcf).to_le_bytes();
+
+        [fcf[0], fcf[1], seq, 0, 0]
+    }
+
+    #[test]
+    fn ack_frame_pending() {
+        assert_eq!(MacHeader::frame_pending(&imm_ack(0, 1)), Some(false));
+        assert_eq!(
+            MacHeader::frame_pending(&imm_ack(FCF_FRAME_PENDING, 1)),
+            Some(true)
+        );
+
+        // An Enh-ACK to a short address
+        let enh_ack = |fcf| {
+            frame(
+                FCF_ACK | FCF_VERSION_2015 | FCF_PAN_ID_COMPRESSION | FCF_DST_SHORT | fcf,
+                1,
+                &[&0x0002_u16.to_le_bytes()],
+            )
+        };
+        assert_eq!(MacHeader::frame_pending(&enh_ack(0)), Some(false));
+        assert_eq!(
+            MacHeader::frame_pending(&enh_ack(FCF_FRAME_PENDING)),
+            Some(true)
+        );
+
+        assert_eq!(MacHeader::frame_pending(&[FCF_ACK as u8]), None);
+    }
+
+    #[test]
+    fn prep_enh_ack() {
+        let fcf = FCF_DATA
+            | FCF_ACK_REQ
+            | FCF_PAN_ID_COMPRESSION
+            | FCF_DST_SHORT
+            | FCF_SRC_SHORT
+            | FCF_VERSION_2015;
+        let addrs: [&[u8]; 3] = [
+            &0x1234_u16.to_le_bytes(),
+            &0x0001_u16.to_le_bytes(),
+            &0x0002_u16.to_le_bytes(),
+        ];
+
+        let mut ack_buf = [0xff; 127];
+
+        // Addressed to the frame source, with no PAN ID and no source address
+        let ack_fcf = FCF_ACK | FCF_VERSION_2015 | FCF_PAN_ID_COMPRESSION | FCF_DST_SHORT;
+
+        let len = MacHeader::prep_enh_ack(&frame(fcf, 7, &addrs), &[], &mut ack_buf);
+        assert_eq!(len, Some(7));
+        assert_eq!(
+            &ack_buf[..7],
+            &[ack_fcf as u8, (ack_fcf >> 8) as u8, 7, 0x02, 0x00, 0, 0]
+        );
+
+        let ack_fcf = ack_fcf | FCF_IE_PRESENT;
+
+        let len = MacHeader::prep_enh_ack(&frame(fcf, 7, &addrs), &[0x12, 0x34], &mut ack_buf);
+        assert_eq!(len, Some(9));
+        assert_eq!(
+            &ack_buf[..9],
+            &[
+                ack_fcf as u8,
+                (ack_fcf >> 8) as u8,
+                7,
+                0x02,
+                0x00,
+                0x12,
+                0x34,
+                0,
+                0
+            ]
+        );
+
+        // The Enh-ACK does not fit
+        assert_eq!(
+            MacHeader::prep_enh_ack(&frame(fcf, 7, &addrs), &[0x12, 0x34], &mut ack_buf[..8]),
+            None
+        );
+
+        // With a suppressed sequence number, the Enh-ACK has none either
+        let mut psdu = frame(fcf | FCF_SEQ_SUPPRESSION, 0, &addrs);
+        psdu.remove(2);
+
+        let ack_fcf = FCF_ACK
+            | FCF_VERSION_2015
+            | FCF_PAN_ID_COMPRESSION
+            | FCF_DST_SHORT
+            | FCF_SEQ_SUPPRESSION;
+
+        let len = MacHeader::prep_enh_ack(&psdu, &[], &mut ack_buf);
+        assert_eq!(len, Some(6));
+        assert_eq!(
+            &ack_buf[..6],
+            &[ack_fcf as u8, (ack_fcf >> 8) as u8, 0x02, 0x00, 0, 0]
+        );
+
+        // No Enh-ACK for frames without an ACK request, for 2003/2006 frames, or for ACKs
+        for fcf in [
+            fcf & !FCF_ACK_REQ,
+            (fcf & !FCF_VERSION_2015) | FCF_VERSION_2006,
+            (fcf & !FCF_DATA) | FCF_ACK,
+        ] {
+            assert_eq!(
+                MacHeader::prep_enh_ack(&frame(fcf, 7, &addrs), &[], &mut ack_buf),
+                None
+            );
+        }
+    }
+
+    /// Receive from the radio until it runs out of frames, returning the sequence numbers
+    /// of the received frames.
+    fn receive_all<R>(radio: &mut R) -> heapless::Vec<u8, 16>
+    where
+        R: Radio<Error = RadioErrorKind>,
+    {
+        let mut seqs = heapless::Vec::new();
+        let mut psdu_buf = [0; 127];
+
+        while let Ok(psdu_meta) = block_on(radio.receive(&mut psdu_buf)) {
+            assert!(psdu_meta.len > 2);
+            seqs.push(psdu_buf[2]).unwrap();
+        }
+
+        seqs
+    }
+
+    #[test]
+    fn duplicate_filter() {
+        let a1 = data_frame(0x1234, 0x0001, 0x000a, 1);
+        let a2 = data_frame(0x1234, 0x0001, 0x000a, 2);
+        let b2 = data_frame(0x1234, 0x0001, 0x000b, 2);
+        let b3 = data_frame(0x1234, 0x0001, 0x000b, 3);
+
+        let frames: [&[u8]; 6] = [&a1, &a1, &a2, &b2, &a2, &b3];
+        let mut radio = DuplicateFilterRadio::<_, 4>::new(TestRadio { frames: &frames });
+
+        // The repeated sequence numbers of a source are dropped, also when
+        // another source sent a frame in between
+        assert_eq!(receive_all(&mut radio), [1, 2, 2, 3]);
+        assert_eq!(radio.duplicates(), 2);
+    }
+
+    #[test]
+    fn duplicate_filter_full_table() {
+        let a1 = data_frame(0x1234, 0x0001, 0x000a, 1);
+        let b1 = data_frame(0x1234, 0x0001, 0x000b, 1);
+
+        // With a single slot, `b1` replaces the entry of `a1`
+        let frames: [&[u8]; 3] = [&a1, &b1, &a1];
+        let mut radio = DuplicateFilterRadio::<_, 1>::new(TestRadio { frames: &frames });
+
+        assert_eq!(receive_all(&mut radio), [1, 1, 1]);
+        assert_eq!(radio.duplicates(), 0);
+    }
+
+    #[test]
+    fn duplicate_filter_no_src_addr() {
+        let no_src = frame(
+            FCF_DATA | FCF_DST_SHORT | FCF_VERSION_2006,
+            1,
+            &[&0x1234_u16.to_le_bytes(), &0x0001_u16.to_le_bytes()],
+        );
+
+        let frames: [&[u8]; 2] = [&no_src, &no_src];
+        let mut radio = DuplicateFilterRadio::<_, 4>::new(TestRadio { frames: &frames });
+
+        // Frames without a source address cannot be told apart, so they are let through
+        assert_eq!(receive_all(&mut radio), [1, 1]);
+        assert_eq!(radio.duplicates(), 0);
+    }
+
+    #[test]
+    fn region_max_power() {
+        for (region, max_power) in [(Region::Fcc, 30), (Region::Etsi, 10), (Region::Japan, 10)] {
+            // The band edges of the 2.4 GHz O-QPSK channels
+            assert_eq!(region.max_power(11), Some(max_power));
+            assert_eq!(region.max_power(26), Some(max_power));
+
+            // The channels of the other PHYs (sub-GHz) and beyond
+            assert_eq!(region.max_power(0), None);
+            assert_eq!(region.max_power(10), None);
+            assert_eq!(region.max_power(27), None);
+            assert_eq!(region.max_power(u8::MAX), None);
+        }
+    }
+
+    #[test]
+    fn link_quality_in() {
+        let meta = |rssi| PsduMeta {
+            len: 0,
+            channel: 11,
+            channel_page: 0,
+            rssi,
+            timestamp: None,
+        };
+
+        for (rssi, link_quality) in [
+            (-80, 3),
+            (-81, 2),
+            (-90, 2),
+            (-91, 1),
+            (-98, 1),
+            (-99, 0),
+            (-100, 0),
+            (-110, 0),
+        ] {
+            assert_eq!(
+                meta(Some(rssi)).link_quality_in(-100),
+                link_quality,
+                "RSSI {rssi}"
+            );
+        }
+
+        // No overflow with extreme values
+        assert_eq!(meta(Some(i8::MAX)).link_quality_in(i8::MIN), 3);
+        assert_eq!(meta(Some(i8::MIN)).link_quality_in(i8::MAX), 0);
+
+        assert_eq!(meta(None).link_quality_in(-100), 0);
+    }
+
+    #[test]
+    fn phy_timings() {
+        assert_eq!(phy::SYMBOL_US, 16);
+        assert_eq!(phy::OCTET_US, 32);
+        // `macAckWaitDuration` is 54 symbols for the O-QPSK PHY
+        assert_eq!(phy::ACK_WAIT_SYMBOLS, 54);
+
+        assert_eq!(phy::airtime_us(0), 192);
+        assert_eq!(phy::airtime_us(MacHeader::ACK_PSDU_LEN as _), 352);
+        assert_eq!(phy::airtime_us(phy::MAX_PSDU_LEN), 4256);
+    }
+
+    #[test]
+    fn phy_max_frame_rate() {
+        // 4256 us of airtime + 640 us of LIFS
+        assert_eq!(phy::max_frame_rate(false), (204, 204));
+        // + 864 us of ACK wait for the transmitter, and + 192 us of turnaround + 352 us
+        // of Imm-ACK for the receiver
+        assert_eq!(phy::max_frame_rate(true), (173, 183));
     }
 }
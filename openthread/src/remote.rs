@@ -0,0 +1,1133 @@
+//! A `Radio` tunneled over a datagram (e.g. UDP) socket, for hardware-in-the-loop testing.
+//!
+//! This allows running the OpenThread stack on a host (e.g. under a host debugger) while using
+//! the real RF of a board, which bridges the host to its own radio (e.g. [`crate::esp::EspRadio`])
+//! over Ethernet / Wi-Fi / USB-NCM:
+//!
+//! ```text
+//!   ┌──────────── host ────────────┐       UDP        ┌──────────── board ────────────┐
+//!   │  OpenThread ─ RemoteRadio    │ ◄──────────────► │  RemoteRadioAgent ─ MacRadio  │
+//!   │                  │           │                  │        │            │        │
+//!   │   impl RemoteRadioSocket     │                  │  impl RemoteRadioSocket  EspRadio
+//!   └──────────────────────────────┘                  └───────────────────────────────┘
+//! ```
+//!
+//! Just like with [`crate::ProxyRadio`], the board-side agent wraps its radio in
+//! [`crate::MacRadio`], so that ACKs and address filtering - which are too time-critical
+//! to be tunneled - are always done on the board.
+//!
+//! # Wire protocol
+//!
+//! Each request and each response is exactly one datagram. All multi-byte fields are little-endian.
+//!
+//! Every message starts with a 2-byte header: `[type, token]`. The token is chosen by the host
+//! for each request and is echoed back in the response, so that the host can disregard responses
+//! to requests it had abandoned (for example, a receive request which was cancelled by a transmit
+//! request).
+//!
+//! Requests (host -> board):
+//! - `0x01` Transmit: `[header][config][cca: u8][psdu...]`
+//! - `0x02` Receive: `[header][config]`
+//! - `0x03` Info: `[header][config]`
+//!
+//! where `config` is the radio configuration valid for the request ([`REMOTE_CONFIG_LEN`] bytes):
+//! `[channel: u8][power: i8][cca mode: u8][cca ED threshold: u8][sfd: u8][flags: u8]`
+//! `[pan_id: u16][short_addr: u16][ext_addr: u64][channel settle time: u32]`
+//! `[tx channel: u8][rx channel: u8][beacon order: u8][superframe order: u8][region: u8]`,
+//! with the TX and RX channels being 0 if not set, the region being 0 if not set and 1, 2, 3
+//! for FCC, ETSI and Japan respectively,
+//! and with `flags` being:
+//! bit 0 - promiscuous, bit 1 - RX when idle, bit 2 - drop unsupported frames,
+//! bits 3, 4, 5, 6 - PAN ID, short address, extended address and channel settle time present,
+//! bit 7 - monitor mode.
+//!
+//! Responses (board -> host), with `type` being the request type with bit 7 set:
+//! - `0x81` Transmit done: `[header][status]` if no ACK was received,
+//!   or `[header][status][meta][ack psdu...]` otherwise
+//! - `0x82` Received: `[header][status]` on error,
+//!   or `[header][status][meta][psdu...]` otherwise
+//! - `0x83` Info: `[header][status]` on error, or `[header][status][info]` otherwise
+//!
+//! where `status` is 1 byte: `[error code: u8]` (error code 0 means success), and `meta` is
+//! [`REMOTE_META_LEN`] bytes: `[channel: u8][channel page: u8][rssi present: u8][rssi: i8]`
+//! `[timestamp present: u8][timestamp: u64]`.
+//!
+//! `info` describes the radio on the board ([`REMOTE_INFO_LEN`] bytes), as reported by its
+//! [`Radio`] query methods: `[sensitivity: i8][power step: u8][security levels: u8]`
+//! `[timestamp resolution: u32][tx latency in us: u32][channel bandwidth: u32]`
+//! `[max tx frame rate: u32][max rx frame rate: u32][max power: i8; 3 * 16][frequency: u32; 16]`,
+//! with the maximum power being per region (FCC, ETSI and Japan, in that order) for each of
+//! the channels 11 to 26 (-128 if there is no limit), and the frequency being for each of the channels 11 to 26 (0 if the
+//! channel is not supported).
+//!
+//! A new request cancels the receive request the board is currently processing, if any;
+//! no response is sent for a cancelled request. A transmit request is never cancelled, as
+//! a transmission cannot be aborted cleanly: it is completed (and responded to) first, and
+//! the new request is processed next.
+//!
+//! Datagrams might be lost, so the host repeats a receive request (with the same token) until it
+//! gets a response; the board ignores a request with the same type and token as the request it is
+//! processing. A transmit request is not repeated, as repeating it might transmit the frame twice:
+//! the host gives up on it after a timeout instead. The host also ignores any datagram which is not
+//! the response to its current request (a late duplicate, a response to an abandoned request etc.).
+
+use core::fmt::Debug;
+use core::future::Future;
+use core::mem;
+use core::pin::pin;
+
+use embassy_futures::select::{select, Either};
+
+use embassy_time::{with_timeout, Duration};
+
+use crate::sys::{otRadioCaps, OT_RADIO_FRAME_MAX_SIZE};
+use crate::{
+    Capabilities, Cca, Config, MacCapabilities, MacRadio, MacRadioTimer, PsduMeta, Radio,
+    RadioError, RadioErrorKind, Region, SecurityLevels,
+};
+
+/// The length of the encoded radio configuration in a request.
+pub const REMOTE_CONFIG_LEN: usize = 1 + 1 + 1 + 1 + 1 + 1 + 2 + 2 + 8 + 4 + 1 + 1 + 1 + 1 + 1;
+/// The length of the encoded frame meta-data in a response.
+pub const REMOTE_META_LEN: usize = 1 + 1 + 1 + 1 + 1 + 8;
+/// The length of the encoded radio information in a response.
+pub const REMOTE_INFO_LEN: usize =
+    1 + 1 + 1 + 4 + 4 + 4 + 4 + 4 + REGIONS.len() * CHANNELS + 4 * CHANNELS;
+/// The maximum length of a request or a response datagram.
+pub const REMOTE_MAX_MSG_LEN: usize = HEADER_LEN + REMOTE_CONFIG_LEN + 1 + PSDU_LEN;
+
+const PSDU_LEN: usize = OT_RADIO_FRAME_MAX_SIZE as _;
+
+const HEADER_LEN: usize = 2;
+const STATUS_LEN: usize = 1;
+
+const MSG_TRANSMIT: u8 = 0x01;
+const MSG_RECEIVE: u8 = 0x02;
+const MSG_INFO: u8 = 0x03;
+const MSG_RESPONSE: u8 = 0x80;
+
+const FLAG_PROMISCUOUS: u8 = 1 << 0;
+const FLAG_RX_WHEN_IDLE: u8 = 1 << 1;
+const FLAG_DROP_UNSUPPORTED_FRAMES: u8 = 1 << 2;
+const FLAG_PAN_ID: u8 = 1 << 3;
+const FLAG_SHORT_ADDR: u8 = 1 << 4;
+const FLAG_EXT_ADDR: u8 = 1 << 5;
+const FLAG_CHANNEL_SETTLE: u8 = 1 << 6;
+const FLAG_MONITOR_MODE: u8 = 1 << 7;
+
+/// The first of the channels the radio information is reported for.
+const FIRST_CHANNEL: u8 = 11;
+/// The number of channels the radio information is reported for (11 to 26).
+const CHANNELS: usize = 16;
+/// The regions the maximum power is reported for, in their wire order.
+const REGIONS: [Region; 3] = [Region::Fcc, Region::Etsi, Region::Japan];
+/// The maximum power reported for a channel without a regulatory limit.
+const NO_MAX_POWER: i8 = i8::MIN;
+
+/// The datagram socket over which `RemoteRadio` and `RemoteRadioAgent` talk to each other.
+///
+/// Typically a connected UDP socket (e.g. a `std::net::UdpSocket` wrapper on the host, and an
+/// `embassy-net` UDP socket on the board), but any transport preserving the datagram boundaries
+/// would do. Datagrams might be lost, but not truncated.
+pub trait RemoteRadioSocket {
+    /// The socket error type.
+    type Error: Debug;
+
+    /// Send `data` as one datagram to the peer.
+    fn send(&mut self, data: &[u8]) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Receive one datagram from the peer into `buf`, returning its length.
+    fn recv(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>>;
+}
+
+impl<T> RemoteRadioSocket for &mut T
+where
+    T: RemoteRadioSocket + ?Sized,
+{
+    type Error = T::Error;
+
+    fn send(&mut self, data: &[u8]) -> impl Future<Output = Result<(), Self::Error>> {
+        T::send(self, data)
+    }
+
+    fn recv(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>> {
+        T::recv(self, buf)
+    }
+}
+
+/// An error type for the remote radio.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RemoteRadioError<E> {
+    /// Error coming from the socket
+    Io(E),
+    /// Error reported by the radio on the board
+    Radio(RadioErrorKind),
+    /// No response to a transmit request was received in time (e.g. a datagram was lost)
+    Timeout,
+    /// A malformed message was received
+    Protocol,
+}
+
+impl<E> RadioError for RemoteRadioError<E>
+where
+    E: Debug,
+{
+    fn kind(&self) -> RadioErrorKind {
+        match self {
+            Self::Radio(kind) => *kind,
+            Self::Io(_) | Self::Timeout | Self::Protocol => RadioErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<E> defmt::Format for RemoteRadioError<E>
+where
+    E: Debug,
+{
+    fn format(&self, fmt: defmt::Formatter<'_>) {
+        defmt::write!(fmt, "{}", self.kind())
+    }
+}
+
+/// A `Radio` implementation tunneling all radio operations over a [`RemoteRadioSocket`]
+/// to a [`RemoteRadioAgent`], which drives the actual radio.
+///
+/// The `CAPS` const generic should match the capabilities of the radio on the board.
+///
+/// The query methods of [`Radio`] (e.g. [`Radio::sensitivity_dbm`]) report the values of the
+/// radio on the board, as fetched once by [`RemoteRadio::connect`]. The exceptions are
+/// [`Radio::radio_time_now`], which reports the time of the host (rather than of the board),
+/// and [`Radio::tx_latency`], which does not include the latency of the tunnel itself.
+pub struct RemoteRadio<S, const CAPS: otRadioCaps> {
+    socket: S,
+    config: Config,
+    token: u8,
+    buf: [u8; REMOTE_MAX_MSG_LEN],
+    info: RemoteRadioInfo,
+}
+
+impl<S, const CAPS: otRadioCaps> RemoteRadio<S, CAPS>
+where
+    S: RemoteRadioSocket,
+{
+    /// How long to wait for the response to a transmit request.
+    pub const TX_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// How long to wait for the response to a receive request before repeating the request,
+    /// in case its datagram was lost.
+    pub const RX_REPEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Create a new `RemoteRadio` instance, fetching the information about the radio
+    /// from the board.
+    ///
+    /// Fails with [`RemoteRadioError::Timeout`] if the board does not respond within
+    /// [`Self::TX_TIMEOUT`] (e.g. because its agent is not running yet).
+    ///
+    /// Arguments:
+    /// - `socket`: The socket connected to the board running the `RemoteRadioAgent`.
+    pub async fn connect(socket: S) -> Result<Self, RemoteRadioError<S::Error>> {
+        let mut this = Self {
+            socket,
+            config: Config::new(),
+            token: 0,
+            buf: [0; REMOTE_MAX_MSG_LEN],
+            info: RemoteRadioInfo::UNKNOWN,
+        };
+
+        let token = this.send_request(MSG_INFO, |_| 0).await?;
+
+        let len = with_timeout(Self::TX_TIMEOUT, this.recv_response(MSG_INFO, token))
+            .await
+            .map_err(|_| RemoteRadioError::Timeout)??;
+
+        let offset = HEADER_LEN + STATUS_LEN;
+        if len < offset + REMOTE_INFO_LEN {
+            return Err(RemoteRadioError::Protocol);
+        }
+
+        this.info = decode_info(&this.buf[offset..]);
+
+        Ok(this)
+    }
+
+    /// Send a new request, with the message type and the contents written by `f` after
+    /// the header and the configuration, and return the token of the request.
+    async fn send_request<F>(
+        &mut self,
+        msg_type: u8,
+        f: F,
+    ) -> Result<u8, RemoteRadioError<S::Error>>
+    where
+        F: FnOnce(&mut [u8]) -> usize,
+    {
+        self.token = self.token.wrapping_add(1);
+
+        self.send_request_with_token(msg_type, self.token, f)
+            .await?;
+
+        Ok(self.token)
+    }
+
+    /// Send a request with the given token, with the message type and the contents written
+    /// by `f` after the header and the configuration.
+    async fn send_request_with_token<F>(
+        &mut self,
+        msg_type: u8,
+        token: u8,
+        f: F,
+    ) -> Result<(), RemoteRadioError<S::Error>>
+    where
+        F: FnOnce(&mut [u8]) -> usize,
+    {
+        self.buf[0] = msg_type;
+        self.buf[1] = token;
+        encode_config(&self.config, &mut self.buf[HEADER_LEN..]);
+
+        let offset = HEADER_LEN + REMOTE_CONFIG_LEN;
+        let len = offset + f(&mut self.buf[offset..]);
+
+        trace!("RemoteRadio, sending request {}/{}", msg_type, token);
+
+        self.socket
+            .send(&self.buf[..len])
+            .await
+            .map_err(RemoteRadioError::Io)
+    }
+
+    /// Wait for the response to the request with the given type and token,
+    /// disregarding any other datagram (e.g. stale responses), and return its length.
+    async fn recv_response(
+        &mut self,
+        msg_type: u8,
+        token: u8,
+    ) -> Result<usize, RemoteRadioError<S::Error>> {
+        loop {
+            let len = self
+                .socket
+                .recv(&mut self.buf)
+                .await
+                .map_err(RemoteRadioError::Io)?;
+
+            if len < HEADER_LEN + STATUS_LEN {
+                warn!("RemoteRadio, disregarding malformed datagram, len {}", len);
+                continue;
+            }
+
+            if self.buf[0] == MSG_RESPONSE | msg_type && self.buf[1] == token {
+                decode_status(&self.buf[HEADER_LEN..]).map_err(RemoteRadioError::Radio)?;
+
+                break Ok(len);
+            }
+
+            trace!(
+                "RemoteRadio, disregarding stale datagram {}/{}",
+                self.buf[0],
+                self.buf[1]
+            );
+        }
+    }
+
+    /// Copy the meta-data and the PSDU of a successful response into `psdu_buf`.
+    fn load_psdu(
+        &self,
+        len: usize,
+        psdu_buf: &mut [u8],
+    ) -> Result<PsduMeta, RemoteRadioError<S::Error>> {
+        let offset = HEADER_LEN + STATUS_LEN;
+
+        if len < offset + REMOTE_META_LEN {
+            return Err(RemoteRadioError::Protocol);
+        }
+
+        let psdu = &self.buf[offset + REMOTE_META_LEN..len];
+        if psdu.len() > psdu_buf.len() {
+            return Err(RemoteRadioError::Protocol);
+        }
+
+        psdu_buf[..psdu.len()].copy_from_slice(psdu);
+
+        Ok(decode_meta(&self.buf[offset..], psdu.len()))
+    }
+}
+
+impl<S, const CAPS: otRadioCaps> Radio for RemoteRadio<S, CAPS>
+where
+    S: RemoteRadioSocket,
+{
+    type Error = RemoteRadioError<S::Error>;
+
+    const CAPS: Capabilities = Capabilities::from_bits_truncate(CAPS);
+
+    // ... because the actual radio on the board is wrapped with `MacRadio` by the agent
+    const MAC_CAPS: MacCapabilities = MacCapabilities::all();
+
+    async fn set_config(&mut self, config: &Config) -> Result<(), Self::Error> {
+        // As with `ProxyRadio`, the configuration is sent along with each request
+        self.config = config.clone();
+        Ok(())
+    }
+
+    fn is_promiscuous(&self) -> bool {
+        self.config.is_promiscuous()
+    }
+
+    fn timestamp_resolution_ns(&self) -> u32 {
+        self.info.timestamp_resolution_ns
+    }
+
+    fn sensitivity_dbm(&self) -> i8 {
+        self.info.sensitivity_dbm
+    }
+
+    fn power_step_db(&self) -> u8 {
+        self.info.power_step_db
+    }
+
+    fn supported_security_levels(&self) -> SecurityLevels {
+        self.info.security_levels
+    }
+
+    fn regulatory_max_power(&self, channel: u8, region: Region) -> Option<i8> {
+        let region_index = unwrap!(REGIONS.iter().position(|r| *r == region));
+
+        channel_index(channel).and_then(|index| self.info.max_power[region_index][index])
+    }
+
+    fn tx_latency(&self) -> Duration {
+        Duration::from_micros(self.info.tx_latency_us as _)
+    }
+
+    fn channel_bandwidth_khz(&self) -> u32 {
+        self.info.channel_bandwidth_khz
+    }
+
+    fn channel_to_frequency(&self, channel: u8) -> Option<u32> {
+        channel_index(channel)
+            .map(|index| self.info.frequency_khz[index])
+            .filter(|frequency| *frequency != 0)
+    }
+
+    fn max_frame_rate(&self) -> (u32, u32) {
+        self.info.max_frame_rate
+    }
+
+    async fn transmit(
+        &mut self,
+        psdu: &[u8],
+        cca: bool,
+        ack_psdu_buf: Option<&mut [u8]>,
+    ) -> Result<Option<PsduMeta>, Self::Error> {
+        if psdu.len() > PSDU_LEN {
+            warn!(
+                "RemoteRadio, frame too large to transmit, len {}",
+                psdu.len()
+            );
+            return Err(RemoteRadioError::Radio(RadioErrorKind::Other));
+        }
+
+        let token = self
+            .send_request(MSG_TRANSMIT, |buf| {
+                buf[0] = cca as u8;
+                buf[1..][..psdu.len()].copy_from_slice(psdu);
+
+                1 + psdu.len()
+            })
+            .await?;
+
+        let len = with_timeout(Self::TX_TIMEOUT, self.recv_response(MSG_TRANSMIT, token))
+            .await
+            .map_err(|_| RemoteRadioError::Timeout)??;
+
+        match ack_psdu_buf {
+            Some(ack_psdu_buf) if len > HEADER_LEN + STATUS_LEN => {
+                Ok(Some(self.load_psdu(len, ack_psdu_buf)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn receive(&mut self, psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+        let token = self.send_request(MSG_RECEIVE, |_| 0).await?;
+
+        loop {
+            let result = with_timeout(
+                Self::RX_REPEAT_INTERVAL,
+                self.recv_response(MSG_RECEIVE, token),
+            )
+            .await;
+
+            match result {
+                Ok(len) => break self.load_psdu(len?, psdu_buf),
+                Err(_) => {
+                    // Either the channel is quiet, or a datagram was lost
+                    trace!("RemoteRadio, repeating receive request {}", token);
+
+                    self.send_request_with_token(MSG_RECEIVE, token, |_| 0)
+                        .await?;
+                }
+            }
+        }
+    }
+}
+
+/// The board-side counterpart of [`RemoteRadio`]: receives the requests of the host over
+/// a [`RemoteRadioSocket`], executes them with the actual radio and sends back the responses.
+pub struct RemoteRadioAgent<S> {
+    socket: S,
+}
+
+impl<S> RemoteRadioAgent<S>
+where
+    S: RemoteRadioSocket,
+{
+    /// Create a new `RemoteRadioAgent` instance.
+    ///
+    /// Arguments:
+    /// - `socket`: The socket connected to the host running the stack with `RemoteRadio`.
+    pub const fn new(socket: S) -> Self {
+        Self { socket }
+    }
+
+    /// Run the agent with the provided radio.
+    ///
+    /// Only returns in case of a socket error. Malformed requests are logged and ignored.
+    ///
+    /// Arguments:
+    /// - `radio`: The radio to drive.
+    /// - `delay`: The delay implementation to use for the `MacRadio` wrapping the radio.
+    pub async fn run<R, T>(&mut self, radio: R, delay: T) -> Result<(), S::Error>
+    where
+        R: Radio,
+        T: MacRadioTimer,
+    {
+        let mut radio = MacRadio::new(radio, delay);
+
+        let mut request = [0_u8; REMOTE_MAX_MSG_LEN];
+        let mut next_request = [0_u8; REMOTE_MAX_MSG_LEN];
+        let mut response = [0_u8; REMOTE_MAX_MSG_LEN];
+
+        let mut pending_len = None;
+
+        debug!("RemoteRadioAgent, running");
+
+        loop {
+            let len = match pending_len.take() {
+                Some(len) => len,
+                None => self.socket.recv(&mut request).await?,
+            };
+
+            let (response_len, next_len) = {
+                let mut process = pin!(Self::process(&mut radio, &request[..len], &mut response));
+
+                loop {
+                    let next = self.socket.recv(&mut next_request);
+
+                    match select(&mut process, next).await {
+                        Either::First(response_len) => break (response_len, None),
+                        Either::Second(next_len) => {
+                            let next_len = next_len?;
+
+                            if len >= HEADER_LEN
+                                && next_len >= HEADER_LEN
+                                && next_request[..HEADER_LEN] == request[..HEADER_LEN]
+                            {
+                                trace!("RemoteRadioAgent, ignoring repeated request");
+                                continue;
+                            }
+
+                            if request[0] == MSG_TRANSMIT {
+                                // Dropping the transmission would leave the radio busy with it
+                                trace!("RemoteRadioAgent, completing TX before the next request");
+                                break ((&mut process).await, Some(next_len));
+                            }
+
+                            // A new request cancels the one being processed
+                            trace!("RemoteRadioAgent, request cancelled by a new request");
+                            break (None, Some(next_len));
+                        }
+                    }
+                }
+            };
+
+            if let Some(response_len) = response_len {
+                self.socket.send(&response[..response_len]).await?;
+            }
+
+            if let Some(next_len) = next_len {
+                pending_len = Some(next_len);
+                mem::swap(&mut request, &mut next_request);
+            }
+        }
+    }
+
+    /// Process a single request, returning the length of the response,
+    /// or `None` if the request is malformed.
+    async fn process<R>(radio: &mut R, request: &[u8], response: &mut [u8]) -> Option<usize>
+    where
+        R: Radio,
+    {
+        if request.len() < HEADER_LEN + REMOTE_CONFIG_LEN {
+            warn!("RemoteRadioAgent, malformed request, len {}", request.len());
+            return None;
+        }
+
+        let msg_type = request[0];
+        let config = decode_config(&request[HEADER_LEN..]);
+        let payload = &request[HEADER_LEN + REMOTE_CONFIG_LEN..];
+
+        trace!(
+            "RemoteRadioAgent, processing request {}/{}",
+            msg_type,
+            request[1]
+        );
+
+        response[0] = MSG_RESPONSE | msg_type;
+        response[1] = request[1];
+
+        let (status, meta) = &mut response[HEADER_LEN..].split_at_mut(STATUS_LEN);
+
+        let result = match radio.set_config(&config).await.map_err(|e| e.kind()) {
+            Err(e) => Err(e),
+            Ok(()) => match msg_type {
+                MSG_TRANSMIT if payload.is_empty() => {
+                    warn!("RemoteRadioAgent, transmit request without a CCA flag");
+                    Err(RadioErrorKind::TxInvalid)
+                }
+                MSG_TRANSMIT => {
+                    let (meta, psdu_buf) = meta.split_at_mut(REMOTE_META_LEN);
+
+                    radio
+                        .transmit(
+                            &payload[1..],
+                            payload[0] != 0,
+                            Some(&mut psdu_buf[..PSDU_LEN]),
+                        )
+                        .await
+                        .map(|psdu_meta| psdu_meta.map(|psdu_meta| encode_meta(&psdu_meta, meta)))
+                        .map_err(|e| e.kind())
+                }
+                MSG_RECEIVE => {
+                    let (meta, psdu_buf) = meta.split_at_mut(REMOTE_META_LEN);
+
+                    radio
+                        .receive(&mut psdu_buf[..PSDU_LEN])
+                        .await
+                        .map(|psdu_meta| Some(encode_meta(&psdu_meta, meta)))
+                        .map_err(|e| e.kind())
+                }
+                MSG_INFO => Ok(Some(encode_info(radio, meta))),
+                _ => {
+                    warn!("RemoteRadioAgent, unknown request {}", msg_type);
+                    return None;
+                }
+            },
+        };
+
+        let len = match result {
+            Ok(meta_len) => {
+                encode_status(None, status);
+                meta_len.unwrap_or(0)
+            }
+            Err(e) => {
+                encode_status(Some(e), status);
+                0
+            }
+        };
+
+        Some(HEADER_LEN + STATUS_LEN + len)
+    }
+}
+
+fn encode_config(config: &Config, buf: &mut [u8]) {
+    let (cca_mode, ed_threshold) = match config.cca {
+        Cca::Carrier => (0, 0),
+        Cca::Ed { ed_threshold } => (1, ed_threshold),
+        Cca::CarrierAndEd { ed_threshold } => (2, ed_threshold),
+        Cca::CarrierOrEd { ed_threshold } => (3, ed_threshold),
+    };
+
+    let mut flags = 0;
+
+    if config.promiscuous {
+        flags |= FLAG_PROMISCUOUS;
+    }
+
+    if config.rx_when_idle {
+        flags |= FLAG_RX_WHEN_IDLE;
+    }
+
+    if config.drop_unsupported_frames {
+        flags |= FLAG_DROP_UNSUPPORTED_FRAMES;
+    }
+
+    if config.pan_id.is_some() {
+        flags |= FLAG_PAN_ID;
+    }
+
+    if config.short_addr.is_some() {
+        flags |= FLAG_SHORT_ADDR;
+    }
+
+    if config.ext_addr.is_some() {
+        flags |= FLAG_EXT_ADDR;
+    }
+
+    if config.channel_settle_us.is_some() {
+        flags |= FLAG_CHANNEL_SETTLE;
+    }
+
+    if config.monitor_mode {
+        flags |= FLAG_MONITOR_MODE;
+    }
+
+    buf[0] = config.channel;
+    buf[1] = config.power as u8;
+    buf[2] = cca_mode;
+    buf[3] = ed_threshold;
+    buf[4] = config.sfd;
+    buf[5] = flags;
+    buf[6..8].copy_from_slice(&config.pan_id.unwrap_or(0).to_le_bytes());
+    buf[8..10].copy_from_slice(&config.short_addr.unwrap_or(0).to_le_bytes());
+    buf[10..18].copy_from_slice(&config.ext_addr.unwrap_or(0).to_le_bytes());
+    buf[18..22].copy_from_slice(&config.channel_settle_us.unwrap_or(0).to_le_bytes());
+    buf[22] = config.tx_channel.unwrap_or(0);
+    buf[23] = config.rx_channel.unwrap_or(0);
+    buf[24] = config.beacon_order;
+    buf[25] = config.superframe_order;
+    buf[26] = match config.region {
+        None => 0,
+        Some(Region::Fcc) => 1,
+        Some(Region::Etsi) => 2,
+        Some(Region::Japan) => 3,
+    };
+}
+
+fn decode_config(buf: &[u8]) -> Config {
+    let flags = buf[5];
+    let ed_threshold = buf[3];
+
+    let mut config = Config::new();
+
+    config.channel = buf[0];
+    config.power = buf[1] as i8;
+    config.cca = match buf[2] {
+        1 => Cca::Ed { ed_threshold },
+        2 => Cca::CarrierAndEd { ed_threshold },
+        3 => Cca::CarrierOrEd { ed_threshold },
+        _ => Cca::Carrier,
+    };
+    config.sfd = buf[4];
+    config.promiscuous = flags & FLAG_PROMISCUOUS != 0;
+    config.rx_when_idle = flags & FLAG_RX_WHEN_IDLE != 0;
+    config.drop_unsupported_frames = flags & FLAG_DROP_UNSUPPORTED_FRAMES != 0;
+    config.monitor_mode = flags & FLAG_MONITOR_MODE != 0;
+    config.pan_id = (flags & FLAG_PAN_ID != 0).then(|| u16::from_le_bytes([buf[6], buf[7]]));
+    config.short_addr =
+        (flags & FLAG_SHORT_ADDR != 0).then(|| u16::from_le_bytes([buf[8], buf[9]]));
+    config.ext_addr =
+        (flags & FLAG_EXT_ADDR != 0).then(|| u64::from_le_bytes(unwrap!(buf[10..18].try_into())));
+    config.channel_settle_us = (flags & FLAG_CHANNEL_SETTLE != 0)
+        .then(|| u32::from_le_bytes(unwrap!(buf[18..22].try_into())));
+    config.tx_channel = (buf[22] != 0).then_some(buf[22]);
+    config.rx_channel = (buf[23] != 0).then_some(buf[23]);
+    config.beacon_order = buf[24];
+    config.superframe_order = buf[25];
+    config.region = match buf[26] {
+        1 => Some(Region::Fcc),
+        2 => Some(Region::Etsi),
+        3 => Some(Region::Japan),
+        _ => None,
+    };
+
+    config
+}
+
+/// Encode the meta-data of a frame, returning the length of the meta-data and the PSDU.
+fn encode_meta(psdu_meta: &PsduMeta, buf: &mut [u8]) -> usize {
+    buf[0] = psdu_meta.channel;
+    buf[1] = psdu_meta.channel_page;
+    buf[2] = psdu_meta.rssi.is_some() as u8;
+    buf[3] = psdu_meta.rssi.unwrap_or(0) as u8;
+    buf[4] = psdu_meta.timestamp.is_some() as u8;
+    buf[5..13].copy_from_slice(&psdu_meta.timestamp.unwrap_or(0).to_le_bytes());
+
+    REMOTE_META_LEN + psdu_meta.len
+}
+
+fn decode_meta(buf: &[u8], len: usize) -> PsduMeta {
+    PsduMeta {
+        len,
+        channel: buf[0],
+        channel_page: buf[1],
+        rssi: (buf[2] != 0).then_some(buf[3] as i8),
+        timestamp: (buf[4] != 0).then(|| u64::from_le_bytes(unwrap!(buf[5..13].try_into()))),
+    }
+}
+
+/// The information about the radio on the board, as fetched by [`RemoteRadio::connect`].
+struct RemoteRadioInfo {
+    sensitivity_dbm: i8,
+    power_step_db: u8,
+    security_levels: SecurityLevels,
+    timestamp_resolution_ns: u32,
+    tx_latency_us: u32,
+    channel_bandwidth_khz: u32,
+    max_frame_rate: (u32, u32),
+    max_power: [[Option<i8>; CHANNELS]; REGIONS.len()],
+    frequency_khz: [u32; CHANNELS],
+}
+
+impl RemoteRadioInfo {
+    /// A placeholder until the information is fetched.
+    const UNKNOWN: Self = Self {
+        sensitivity_dbm: 0,
+        power_step_db: 0,
+        security_levels: SecurityLevels::empty(),
+        timestamp_resolution_ns: 0,
+        tx_latency_us: 0,
+        channel_bandwidth_khz: 0,
+        max_frame_rate: (0, 0),
+        max_power: [[None; CHANNELS]; REGIONS.len()],
+        frequency_khz: [0; CHANNELS],
+    };
+}
+
+/// Return the index of the channel in the radio information,
+/// or `None` if the information does not cover the channel.
+fn channel_index(channel: u8) -> Option<usize> {
+    let index = channel.checked_sub(FIRST_CHANNEL)? as usize;
+
+    (index < CHANNELS).then_some(index)
+}
+
+/// Encode the information about the radio, returning its length.
+fn encode_info<R>(radio: &R, buf: &mut [u8]) -> usize
+where
+    R: Radio,
+{
+    let (max_tx_frame_rate, max_rx_frame_rate) = radio.max_frame_rate();
+
+    buf[0] = radio.sensitivity_dbm() as u8;
+    buf[1] = radio.power_step_db();
+    buf[2] = radio.supported_security_levels().bits();
+    buf[3..7].copy_from_slice(&radio.timestamp_resolution_ns().to_le_bytes());
+    buf[7..11].copy_from_slice(&(radio.tx_latency().as_micros() as u32).to_le_bytes());
+    buf[11..15].copy_from_slice(&radio.channel_bandwidth_khz().to_le_bytes());
+    buf[15..19].copy_from_slice(&max_tx_frame_rate.to_le_bytes());
+    buf[19..23].copy_from_slice(&max_rx_frame_rate.to_le_bytes());
+
+    let (max_power, frequency) = buf[23..REMOTE_INFO_LEN].split_at_mut(REGIONS.len() * CHANNELS);
+
+    for (region, max_power) in REGIONS.iter().zip(max_power.chunks_exact_mut(CHANNELS)) {
+        for (channel, max_power) in (FIRST_CHANNEL..).zip(max_power.iter_mut()) {
+            *max_power = radio
+                .regulatory_max_power(channel, *region)
+                .unwrap_or(NO_MAX_POWER) as u8;
+        }
+    }
+
+    for (channel, frequency) in (FIRST_CHANNEL..).zip(frequency.chunks_exact_mut(4)) {
+        frequency.copy_from_slice(
+            &radio
+                .channel_to_frequency(channel)
+                .unwrap_or(0)
+                .to_le_bytes(),
+        );
+    }
+
+    REMOTE_INFO_LEN
+}
+
+fn decode_info(buf: &[u8]) -> RemoteRadioInfo {
+    let u32_at = |offset: usize| u32::from_le_bytes(unwrap!(buf[offset..offset + 4].try_into()));
+
+    let mut info = RemoteRadioInfo {
+        sensitivity_dbm: buf[0] as i8,
+        power_step_db: buf[1],
+        security_levels: SecurityLevels::from_bits_truncate(buf[2]),
+        timestamp_resolution_ns: u32_at(3),
+        tx_latency_us: u32_at(7),
+        channel_bandwidth_khz: u32_at(11),
+        max_frame_rate: (u32_at(15), u32_at(19)),
+        ..RemoteRadioInfo::UNKNOWN
+    };
+
+    let (max_power, frequency) = buf[23..REMOTE_INFO_LEN].split_at(REGIONS.len() * CHANNELS);
+
+    for (info_max_power, max_power) in info
+        .max_power
+        .iter_mut()
+        .zip(max_power.chunks_exact(CHANNELS))
+    {
+        for (info_max_power, max_power) in info_max_power.iter_mut().zip(max_power) {
+            *info_max_power = Some(*max_power as i8).filter(|max_power| *max_power != NO_MAX_POWER);
+        }
+    }
+
+    for (info_frequency, frequency) in info.frequency_khz.iter_mut().zip(frequency.chunks_exact(4))
+    {
+        *info_frequency = u32::from_le_bytes(unwrap!(frequency.try_into()));
+    }
+
+    info
+}
+
+fn encode_status(error: Option<RadioErrorKind>, buf: &mut [u8]) {
+    buf[0] = match error {
+        None => 0,
+        Some(RadioErrorKind::TxInvalid) => 1,
+        Some(RadioErrorKind::RxInvalid) => 2,
+        Some(RadioErrorKind::RxFailed) => 3,
+        Some(RadioErrorKind::TxFailed) => 4,
+        Some(RadioErrorKind::TxAckFailed) => 5,
+        Some(RadioErrorKind::RxAckFailed) => 6,
+        Some(RadioErrorKind::TxAckTimeout) => 7,
+        Some(RadioErrorKind::RxAckTimeout) => 8,
+        Some(RadioErrorKind::RxAckInvalid) => 9,
+        Some(RadioErrorKind::DeadlineMissed) => 10,
+        Some(RadioErrorKind::Busy) => 11,
+        Some(RadioErrorKind::Silenced) => 12,
+        Some(RadioErrorKind::ConfigUnsupported) => 13,
+        Some(RadioErrorKind::InvalidFrame) => 14,
+        Some(RadioErrorKind::RateLimited) => 15,
+        Some(RadioErrorKind::BufferTooSmall) => 16,
+        Some(RadioErrorKind::Other) => 0xff,
+    };
+}
+
+fn decode_status(buf: &[u8]) -> Result<(), RadioErrorKind> {
+    let kind = match buf[0] {
+        0 => return Ok(()),
+        1 => RadioErrorKind::TxInvalid,
+        2 => RadioErrorKind::RxInvalid,
+        3 => RadioErrorKind::RxFailed,
+        4 => RadioErrorKind::TxFailed,
+        5 => RadioErrorKind::TxAckFailed,
+        6 => RadioErrorKind::RxAckFailed,
+        7 => RadioErrorKind::TxAckTimeout,
+        8 => RadioErrorKind::RxAckTimeout,
+        9 => RadioErrorKind::RxAckInvalid,
+        10 => RadioErrorKind::DeadlineMissed,
+        11 => RadioErrorKind::Busy,
+        12 => RadioErrorKind::Silenced,
+        13 => RadioErrorKind::ConfigUnsupported,
+        14 => RadioErrorKind::InvalidFrame,
+        15 => RadioErrorKind::RateLimited,
+        16 => RadioErrorKind::BufferTooSmall,
+        _ => RadioErrorKind::Other,
+    };
+
+    Err(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A radio reporting non-default values from its query methods.
+    struct TestRadio;
+
+    impl Radio for TestRadio {
+        type Error = RadioErrorKind;
+
+        const CAPS: Capabilities = Capabilities::empty();
+
+        const MAC_CAPS: MacCapabilities = MacCapabilities::empty();
+
+        fn sensitivity_dbm(&self) -> i8 {
+            -97
+        }
+
+        fn power_step_db(&self) -> u8 {
+            3
+        }
+
+        fn supported_security_levels(&self) -> SecurityLevels {
+            SecurityLevels::all()
+        }
+
+        fn timestamp_resolution_ns(&self) -> u32 {
+            1_000
+        }
+
+        fn tx_latency(&self) -> Duration {
+            Duration::from_micros(250)
+        }
+
+        fn regulatory_max_power(&self, channel: u8, region: Region) -> Option<i8> {
+            // Lower than the region limit on the last channel, and no limit on the first one
+            match channel {
+                11 => None,
+                26 => Some(-5),
+                _ => region.max_power(channel),
+            }
+        }
+
+        fn max_frame_rate(&self) -> (u32, u32) {
+            (150, 160)
+        }
+
+        async fn set_config(&mut self, _config: &Config) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        async fn transmit(
+            &mut self,
+            _psdu: &[u8],
+            _cca: bool,
+            _ack_psdu_buf: Option<&mut [u8]>,
+        ) -> Result<Option<PsduMeta>, Self::Error> {
+            Ok(None)
+        }
+
+        async fn receive(&mut self, _psdu_buf: &mut [u8]) -> Result<PsduMeta, Self::Error> {
+            Err(RadioErrorKind::RxFailed)
+        }
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let mut buf = [0; REMOTE_CONFIG_LEN];
+
+        let config = Config::new();
+        encode_config(&config, &mut buf);
+        assert_eq!(decode_config(&buf), config);
+
+        let config = Config {
+            channel: 26,
+            power: -20,
+            cca: Cca::CarrierOrEd { ed_threshold: 0x42 },
+            sfd: 0xa7,
+            promiscuous: true,
+            rx_when_idle: true,
+            pan_id: Some(0x1234),
+            short_addr: Some(0xfffe),
+            ext_addr: Some(0x0011_2233_4455_6677),
+            drop_unsupported_frames: true,
+            channel_settle_us: Some(0x0102_0304),
+            monitor_mode: true,
+            tx_channel: Some(15),
+            rx_channel: Some(20),
+            beacon_order: 14,
+            superframe_order: 7,
+            region: Some(Region::Japan),
+        };
+        encode_config(&config, &mut buf);
+        assert_eq!(decode_config(&buf), config);
+
+        for cca in [
+            Cca::Carrier,
+            Cca::Ed { ed_threshold: 1 },
+            Cca::CarrierAndEd { ed_threshold: 2 },
+        ] {
+            let config = Config { cca, ..config };
+            encode_config(&config, &mut buf);
+            assert_eq!(decode_config(&buf), config);
+        }
+    }
+
+    #[test]
+    fn meta_round_trip() {
+        let mut buf = [0; REMOTE_META_LEN];
+
+        for psdu_meta in [
+            PsduMeta {
+                len: 0,
+                channel: 11,
+                channel_page: 0,
+                rssi: None,
+                timestamp: None,
+            },
+            PsduMeta {
+                len: 127,
+                channel: 26,
+                channel_page: 2,
+                rssi: Some(-128),
+                timestamp: Some(u64::MAX - 1),
+            },
+        ] {
+            assert_eq!(
+                encode_meta(&psdu_meta, &mut buf),
+                REMOTE_META_LEN + psdu_meta.len
+            );
+            assert_eq!(decode_meta(&buf, psdu_meta.len), psdu_meta);
+        }
+    }
+
+    #[test]
+    fn info_round_trip() {
+        let mut buf = [0; REMOTE_INFO_LEN];
+
+        assert_eq!(encode_info(&TestRadio, &mut buf), REMOTE_INFO_LEN);
+
+        let info = decode_info(&buf);
+
+        assert_eq!(info.sensitivity_dbm, -97);
+        assert_eq!(info.power_step_db, 3);
+        assert_eq!(info.security_levels, SecurityLevels::all());
+        assert_eq!(info.timestamp_resolution_ns, 1_000);
+        assert_eq!(info.tx_latency_us, 250);
+        assert_eq!(
+            info.channel_bandwidth_khz,
+            TestRadio.channel_bandwidth_khz()
+        );
+        assert_eq!(info.max_frame_rate, (150, 160));
+
+        for (region, max_power) in REGIONS.iter().zip(info.max_power.iter()) {
+            for (channel, max_power) in (FIRST_CHANNEL..).zip(max_power.iter()) {
+                assert_eq!(
+                    *max_power,
+                    TestRadio.regulatory_max_power(channel, *region),
+                    "{region:?}, channel {channel}"
+                );
+            }
+        }
+
+        for (channel, frequency) in (FIRST_CHANNEL..).zip(info.frequency_khz.iter()) {
+            assert_eq!(Some(*frequency), TestRadio.channel_to_frequency(channel));
+        }
+    }
+
+    #[test]
+    fn channel_index() {
+        assert_eq!(super::channel_index(10), None);
+        assert_eq!(super::channel_index(11), Some(0));
+        assert_eq!(super::channel_index(26), Some(CHANNELS - 1));
+        assert_eq!(super::channel_index(27), None);
+    }
+
+    #[test]
+    fn status_round_trip() {
+        let mut buf = [0; STATUS_LEN];
+
+        encode_status(None, &mut buf);
+        assert_eq!(decode_status(&buf), Ok(()));
+
+        for kind in [
+            RadioErrorKind::TxInvalid,
+            RadioErrorKind::RxInvalid,
+            RadioErrorKind::RxFailed,
+            RadioErrorKind::TxFailed,
+            RadioErrorKind::TxAckFailed,
+            RadioErrorKind::RxAckFailed,
+            RadioErrorKind::TxAckTimeout,
+            RadioErrorKind::RxAckTimeout,
+            RadioErrorKind::RxAckInvalid,
+            RadioErrorKind::DeadlineMissed,
+            RadioErrorKind::Busy,
+            RadioErrorKind::Silenced,
+            RadioErrorKind::ConfigUnsupported,
+            RadioErrorKind::InvalidFrame,
+            RadioErrorKind::RateLimited,
+            RadioErrorKind::BufferTooSmall,
+            RadioErrorKind::Other,
+        ] {
+            encode_status(Some(kind), &mut buf);
+            assert_eq!(decode_status(&buf), Err(kind));
+        }
+
+        // Status codes from a newer agent
+        assert_eq!(decode_status(&[0x42]), Err(RadioErrorKind::Other));
+    }
+}